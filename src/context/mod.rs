@@ -1,10 +1,11 @@
+#![allow(clippy::needless_borrow, clippy::unnecessary_to_owned)]
+
 extern crate serde_json;
-use serde_json::Number;
 use serde_json::Value as JsonValue;
 
 use crate::expression_parser::operand::Operand;
 
-pub fn get_context_var(name: &String, context: &serde_json::Value) -> serde_json::Value {
+pub fn get_context_var(name: &str, context: &serde_json::Value) -> serde_json::Value {
     let v: Vec<&str> = name.split('.').collect();
 
     let mut cur: &serde_json::Value = &context;
@@ -18,26 +19,31 @@ pub fn get_context_var(name: &String, context: &serde_json::Value) -> serde_json
     cur.clone()
 }
 
-pub fn var_to_operand(name: &String, context: &serde_json::Value) -> Operand {
-    let v = get_context_var(name, &context);
-    match v {
-        JsonValue::String(s) => Operand::Primitive(JsonValue::String(s)), //Operand::String(s),
-        JsonValue::Number(n) => {
-            let n_value = n.as_f64().unwrap_or(0.0);
-
-            Operand::Primitive(JsonValue::Number(Number::from_f64(n_value).unwrap()))
+/// Sets `name` (`variableName.subvariable`), creating missing objects on the way
+pub fn set_context_var(name: &str, context: &mut serde_json::Value, value: serde_json::Value) {
+    let mut cur = context;
+    for key in name.split('.') {
+        if !cur.is_object() {
+            *cur = JsonValue::Object(Default::default());
         }
-        _ => Operand::Primitive(JsonValue::Null),
+        cur = &mut cur[key];
     }
+
+    *cur = value;
+}
+
+pub fn var_to_operand(name: &str, context: &serde_json::Value) -> Operand {
+    Operand::Primitive(get_context_var(name, context))
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use serde_json::Number;
     #[test]
     fn succeeds_get_context_value_as_operand() -> Result<(), String> {
-        let json_str = r#"{ "season": "Fall", "preferences": { "type": "vegetarian" }, "count": 1 }
+        let json_str = r#"{ "season": "Fall", "preferences": { "type": "vegetarian" }, "count": 1, "ratio": 0.5, "id": 9007199254740993, "vegetarian": true, "allergies": ["nuts", 2] }
                 "#;
 
         let context: serde_json::Value = serde_json::from_str(json_str).unwrap();
@@ -46,9 +52,30 @@ mod tests {
         assert_eq!(c, Operand::Primitive(JsonValue::String("Fall".to_owned())));
 
         let num = var_to_operand(&"count".to_owned(), &context);
+        assert_eq!(num, Operand::Primitive(JsonValue::Number(Number::from(1))));
+
+        let float = var_to_operand("ratio", &context);
+        assert_eq!(
+            float,
+            Operand::Primitive(JsonValue::Number(Number::from_f64(0.5).unwrap()))
+        );
+
+        let big = var_to_operand("id", &context);
+        assert_eq!(
+            big,
+            Operand::Primitive(JsonValue::Number(Number::from(9007199254740993u64)))
+        );
+
+        let flag = var_to_operand("vegetarian", &context);
+        assert_eq!(flag, Operand::Primitive(JsonValue::Bool(true)));
+
+        let list = var_to_operand("allergies", &context);
+        assert_eq!(list, Operand::Primitive(serde_json::json!(["nuts", 2])));
+
+        let object = var_to_operand("preferences", &context);
         assert_eq!(
-            num,
-            Operand::Primitive(JsonValue::Number(Number::from_f64(1.0).unwrap()))
+            object,
+            Operand::Primitive(serde_json::json!({ "type": "vegetarian" }))
         );
 
         let missing_val = var_to_operand(&"NoExist.subParam".to_owned(), &context);
@@ -86,4 +113,18 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn succeeds_set_context_value() {
+        let mut context =
+            serde_json::json!({ "season": "Fall", "preferences": { "type": "vegetarian" } });
+
+        set_context_var("preferences.type", &mut context, JsonValue::from("vegan"));
+        set_context_var("order.count", &mut context, JsonValue::from(2));
+
+        assert_eq!(
+            context,
+            serde_json::json!({ "season": "Fall", "preferences": { "type": "vegan" }, "order": { "count": 2 } })
+        );
+    }
 }
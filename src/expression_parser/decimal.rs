@@ -0,0 +1,437 @@
+use serde_json::Number;
+use std::cmp::Ordering;
+use std::fmt;
+
+/*
+    Exact decimal numbers for money calculations.
+    The magnitude is kept as base 10 digits (least significant first) and
+    `scale` digits of it are after the decimal point, so 12.50 is [0, 5, 2, 1] / 2.
+    Values are normalised - no trailing fractional zeros, zero is never negative.
+*/
+
+/// Significant digits kept by division, same as DECIMAL128 used by FEEL
+const DIVISION_PRECISION: usize = 34;
+
+/// Largest exponent accepted by `parse` and scale accepted by `round`, every digit takes a byte
+const MAX_EXPONENT: i64 = 10_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Copy)]
+pub enum NumberMode {
+    /// f64 arithmetic
+    #[default]
+    Float,
+    /// exact decimal arithmetic on the number text
+    Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    scale: usize,
+}
+
+impl Decimal {
+    /// Parses "12", "-0.25" and "1.5e3"
+    pub fn parse(text: &str) -> Result<Decimal, String> {
+        let err = || format!("invalid decimal \"{}\"", text);
+
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((m, e)) => (m, e.parse::<i64>().map_err(|_| err())?),
+            None => (unsigned, 0),
+        };
+        if !(-MAX_EXPONENT..=MAX_EXPONENT).contains(&exponent) {
+            return Err(format!("exponent of \"{}\" is out of range", text));
+        }
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(err());
+        }
+
+        let mut digits = Vec::with_capacity(whole.len() + fraction.len());
+        for c in whole.chars().chain(fraction.chars()).rev() {
+            digits.push(c.to_digit(10).ok_or_else(err)? as u8);
+        }
+
+        let mut scale = fraction.len() as i64 - exponent;
+        if scale < 0 {
+            let mut shifted = vec![0; (-scale) as usize];
+            shifted.append(&mut digits);
+            digits = shifted;
+            scale = 0;
+        }
+
+        Ok(Decimal::new(negative, digits, scale as usize))
+    }
+
+    pub fn from_number(n: &Number) -> Option<Decimal> {
+        Decimal::parse(&n.to_string()).ok()
+    }
+
+    /// Closest JSON number, integers that fit i64/u64 stay exact
+    pub fn to_number(&self) -> Option<Number> {
+        serde_json::from_str::<Number>(&self.to_string()).ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse::<f64>().unwrap_or(0.0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn add(&self, other: &Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.digits_at(scale), other.digits_at(scale));
+
+        if self.negative == other.negative {
+            return Decimal::new(self.negative, add_digits(&a, &b), scale);
+        }
+
+        match cmp_digits(&a, &b) {
+            Ordering::Less => Decimal::new(other.negative, sub_digits(&b, &a), scale),
+            _ => Decimal::new(self.negative, sub_digits(&a, &b), scale),
+        }
+    }
+
+    pub fn sub(&self, other: &Decimal) -> Decimal {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Decimal) -> Decimal {
+        Decimal::new(
+            self.negative != other.negative,
+            mul_digits(&self.digits, &other.digits),
+            self.scale + other.scale,
+        )
+    }
+
+    /// None on division by zero
+    pub fn div(&self, other: &Decimal) -> Option<Decimal> {
+        if other.is_zero() {
+            return None;
+        }
+
+        // shift the dividend so the quotient has enough significant digits, plus one to round
+        let shift = (DIVISION_PRECISION + other.digits.len() + 1).saturating_sub(self.digits.len());
+        let mut dividend = vec![0; shift];
+        dividend.extend_from_slice(&self.digits);
+
+        let (quotient, _) = divmod_digits(&dividend, &other.digits);
+        let scale = self.scale + shift;
+        let (quotient, scale) = if scale >= other.scale {
+            (quotient, scale - other.scale)
+        } else {
+            let mut shifted = vec![0; other.scale - scale];
+            shifted.extend_from_slice(&quotient);
+            (shifted, 0)
+        };
+
+        let exact = Decimal::new(self.negative != other.negative, quotient, scale);
+        let significant = exact.digits.len();
+        if significant > DIVISION_PRECISION && exact.scale > 0 {
+            let drop = (significant - DIVISION_PRECISION).min(exact.scale);
+            return Some(exact.round(exact.scale as i64 - drop as i64));
+        }
+
+        Some(exact)
+    }
+
+    pub fn neg(&self) -> Decimal {
+        Decimal::new(!self.negative, self.digits.clone(), self.scale)
+    }
+
+    pub fn abs(&self) -> Decimal {
+        Decimal::new(false, self.digits.clone(), self.scale)
+    }
+
+    /// Rounds half away from zero to `scale` fractional digits, negative scale rounds to tens etc.
+    pub fn round(&self, scale: i64) -> Decimal {
+        self.round_with(scale, |first_dropped, _| first_dropped >= 5)
+    }
+
+    pub fn floor(&self) -> Decimal {
+        let negative = self.negative;
+        self.round_with(0, |_, inexact| negative && inexact)
+    }
+
+    pub fn ceiling(&self) -> Decimal {
+        let negative = self.negative;
+        self.round_with(0, |_, inexact| !negative && inexact)
+    }
+
+    /// Truncates to `scale` digits, `away` decides from (first dropped digit, any dropped non zero)
+    /// whether the magnitude is increased by one unit
+    fn round_with(&self, scale: i64, away: impl Fn(u8, bool) -> bool) -> Decimal {
+        let scale = scale.clamp(-MAX_EXPONENT, MAX_EXPONENT);
+        if scale >= self.scale as i64 {
+            return self.clone();
+        }
+
+        let drop = (self.scale as i64 - scale) as usize;
+        let mut digits = self.digits.clone();
+        if digits.len() < drop + 1 {
+            digits.resize(drop + 1, 0);
+        }
+
+        let first_dropped = digits[drop - 1];
+        let inexact = digits[..drop].iter().any(|d| *d != 0);
+        let mut kept = digits.split_off(drop);
+        if away(first_dropped, inexact) {
+            kept = add_digits(&kept, &[1]);
+        }
+
+        if scale < 0 {
+            let mut shifted = vec![0; (-scale) as usize];
+            shifted.append(&mut kept);
+            return Decimal::new(self.negative, shifted, 0);
+        }
+
+        Decimal::new(self.negative, kept, scale as usize)
+    }
+
+    fn new(negative: bool, mut digits: Vec<u8>, mut scale: usize) -> Decimal {
+        let leading_zeros = digits.iter().take(scale).take_while(|d| **d == 0).count();
+        digits.drain(..leading_zeros);
+        scale -= leading_zeros;
+
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        if digits.is_empty() {
+            scale = 0;
+        }
+
+        Decimal {
+            negative: negative && !digits.is_empty(),
+            digits,
+            scale,
+        }
+    }
+
+    fn digits_at(&self, scale: usize) -> Vec<u8> {
+        let mut digits = vec![0; scale - self.scale];
+        digits.extend_from_slice(&self.digits);
+        digits
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let scale = self.scale.max(other.scale);
+                let magnitude = cmp_digits(&self.digits_at(scale), &other.digits_at(scale));
+                if negative {
+                    magnitude.reverse()
+                } else {
+                    magnitude
+                }
+            }
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let mut digits = self.digits.clone();
+        if digits.len() <= self.scale {
+            digits.resize(self.scale + 1, 0);
+        }
+
+        for (i, d) in digits.iter().enumerate().rev() {
+            write!(f, "{}", d)?;
+            if i == self.scale && i > 0 {
+                write!(f, ".")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn trim(mut digits: Vec<u8>) -> Vec<u8> {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+fn cmp_digits(a: &[u8], b: &[u8]) -> Ordering {
+    let (a, b) = (trim(a.to_vec()), trim(b.to_vec()));
+
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+
+    for i in 0..a.len().max(b.len()) {
+        let sum = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        res.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        res.push(carry);
+    }
+
+    res
+}
+
+/// a - b, where a >= b
+fn sub_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+
+    for (i, digit) in a.iter().enumerate() {
+        let mut d = *digit as i8 - *b.get(i).unwrap_or(&0) as i8 - borrow;
+        borrow = 0;
+        if d < 0 {
+            d += 10;
+            borrow = 1;
+        }
+        res.push(d as u8);
+    }
+
+    trim(res)
+}
+
+fn mul_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut res = vec![0u32; a.len() + b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            res[i + j] += *x as u32 * *y as u32;
+        }
+    }
+
+    let mut carry = 0;
+    for d in res.iter_mut() {
+        *d += carry;
+        carry = *d / 10;
+        *d %= 10;
+    }
+
+    trim(res.into_iter().map(|d| d as u8).collect())
+}
+
+/// Long division, returns (quotient, remainder)
+fn divmod_digits(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u8> = vec![];
+
+    for i in (0..a.len()).rev() {
+        remainder.insert(0, a[i]);
+        remainder = trim(remainder);
+
+        let mut count = 0;
+        while cmp_digits(&remainder, b) != Ordering::Less {
+            remainder = sub_digits(&remainder, b);
+            count += 1;
+        }
+        quotient[i] = count;
+    }
+
+    (trim(quotient), remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints_decimals() {
+        let cases = vec![
+            ("0.1", "0.1"),
+            ("-12.50", "-12.5"),
+            ("007", "7"),
+            (".5", "0.5"),
+            ("1.5e3", "1500"),
+            ("25e-4", "0.0025"),
+            ("-0.0", "0"),
+            (
+                "123456789012345678901234567890",
+                "123456789012345678901234567890",
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(d(text).to_string(), expected);
+        }
+
+        assert!(Decimal::parse("1.2.3").is_err());
+        assert!(Decimal::parse("").is_err());
+        assert!(Decimal::parse("1e").is_err());
+    }
+
+    #[test]
+    fn fails_exponent_out_of_range() {
+        assert!(Decimal::parse("1e99999999999999").is_err());
+        assert!(Decimal::parse("1e-99999999999999").is_err());
+        assert_eq!(d("1e10000").to_string().len(), 10_001);
+        assert_eq!(d("1234").round(-99999999999999).to_string(), "0");
+    }
+
+    #[test]
+    fn succeeds_exact_arithmetic() {
+        assert_eq!(d("0.1").add(&d("0.2")), d("0.3"));
+        assert_eq!(d("0.1").sub(&d("0.25")).to_string(), "-0.15");
+        assert_eq!(d("-1.5").mul(&d("-0.2")).to_string(), "0.3");
+        assert_eq!(
+            d("9007199254740993").add(&d("1")).to_string(),
+            "9007199254740994"
+        );
+        assert_eq!(d("10").div(&d("4")), Some(d("2.5")));
+        assert_eq!(
+            d("1").div(&d("3")).unwrap().to_string(),
+            "0.3333333333333333333333333333333333"
+        );
+        assert_eq!(
+            d("2").div(&d("3")).unwrap().to_string(),
+            "0.6666666666666666666666666666666667"
+        );
+        assert_eq!(d("1").div(&d("0")), None);
+    }
+
+    #[test]
+    fn succeeds_rounding() {
+        assert_eq!(d("2.345").round(2).to_string(), "2.35");
+        assert_eq!(d("-2.345").round(2).to_string(), "-2.35");
+        assert_eq!(d("2.344").round(2).to_string(), "2.34");
+        assert_eq!(d("1250").round(-2).to_string(), "1300");
+        assert_eq!(d("2.7").floor().to_string(), "2");
+        assert_eq!(d("-2.1").floor().to_string(), "-3");
+        assert_eq!(d("2.1").ceiling().to_string(), "3");
+        assert_eq!(d("-2.7").ceiling().to_string(), "-2");
+    }
+
+    #[test]
+    fn compares_decimals() {
+        assert!(d("0.3") > d("0.29999999999999999999"));
+        assert!(d("-2") < d("-1.5"));
+        assert_eq!(d("1.50").cmp(&d("1.5")), Ordering::Equal);
+    }
+}
@@ -1,15 +1,29 @@
+#![allow(clippy::needless_borrow)]
+
+use super::decimal::{Decimal, NumberMode};
+use super::functions::call;
 use super::operand::{Operand, Operator};
-use super::tokenizer::Tokenizer;
-use serde_json::{Number, Value};
+use crate::context::var_to_operand;
+use serde_json::Value;
+
+pub fn interpret(postfix: &[Operand], context: &Value) -> Result<Vec<Operand>, String> {
+    interpret_with_mode(postfix, context, NumberMode::Float)
+}
 
-pub fn interpret(postfix: &Vec<Operand>) -> Vec<Operand> {
+/// In `NumberMode::Decimal` context numbers are read as exact decimals
+pub fn interpret_with_mode(
+    postfix: &[Operand],
+    context: &Value,
+    number_mode: NumberMode,
+) -> Result<Vec<Operand>, String> {
     let mut stack: Vec<Operand> = Vec::with_capacity(postfix.len());
 
     for p in postfix {
         match p {
             Operand::OperatorToken(o) => {
-                let r = stack.pop().unwrap();
-                let l = stack.pop().unwrap();
+                let (Some(r), Some(l)) = (stack.pop(), stack.pop()) else {
+                    return Err(format!("operator {:?} is missing an operand", o));
+                };
 
                 match o {
                     Operator::Plus => stack.push(l + r),
@@ -24,21 +38,30 @@ pub fn interpret(postfix: &Vec<Operand>) -> Vec<Operand> {
 
                     Operator::LE => stack.push(Operand::Primitive(Value::Bool(l <= r))),
 
-                    Operator::E => stack.push(Operand::Primitive(Value::Bool(l == r))),
+                    Operator::E => stack.push(Operand::Primitive(Value::Bool(l.equals(&r)))),
 
-                    Operator::NE => stack.push(Operand::Primitive(Value::Bool(l != r))),
+                    Operator::NE => stack.push(Operand::Primitive(Value::Bool(!l.equals(&r)))),
 
                     Operator::Multiply => stack.push(l * r),
 
                     Operator::Division => stack.push(l / r),
                 }
             }
-            Operand::Variable(_var_name) => {
-                // Todo - add json context to this function, and extract var_name from it
-                // temp hack
-                stack.push(Operand::Primitive(Value::Number(
-                    Number::from_f64(2.0).unwrap(),
-                )));
+            Operand::Variable(var_name) => match (var_to_operand(var_name, context), number_mode) {
+                (Operand::Primitive(Value::Number(n)), NumberMode::Decimal) => {
+                    stack.push(match Decimal::from_number(&n) {
+                        Some(d) => Operand::Decimal(d),
+                        None => Operand::Primitive(Value::Null),
+                    });
+                }
+                (operand, _) => stack.push(operand),
+            },
+            Operand::Function(name, arity) => {
+                if stack.len() < *arity {
+                    return Err(format!("function \"{}\" is missing arguments", name));
+                }
+                let args = stack.split_off(stack.len() - arity);
+                stack.push(call(name, args)?);
             }
             _ => {
                 stack.push(p.clone().to_owned());
@@ -46,13 +69,15 @@ pub fn interpret(postfix: &Vec<Operand>) -> Vec<Operand> {
         }
     }
 
-    stack
+    Ok(stack)
 }
 
 #[cfg(test)]
 mod tests {
 
+    use super::super::tokenizer::Tokenizer;
     use super::*;
+    use serde_json::Number;
 
     fn postfix_for(formula: &str) -> Result<Vec<Operand>, String> {
         let mut tokenizer = Tokenizer::new(&formula);
@@ -60,15 +85,17 @@ mod tests {
         tokenizer.to_postfix()
     }
 
+    fn eval(formula: &str) -> Result<Vec<Operand>, String> {
+        interpret(&postfix_for(formula)?, &Value::Null)
+    }
+
     #[test]
     fn interpreter_succeeds_adding() -> Result<(), String> {
         let postfix = postfix_for("2+1");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(
             formula_result,
-            [Operand::Primitive(Value::Number(
-                Number::from_f64(3.0).unwrap()
-            ))]
+            [Operand::Primitive(Value::Number(Number::from(3)))]
         );
         Ok(())
     }
@@ -77,7 +104,7 @@ mod tests {
     fn interpreter_succeeds_adding_string() -> Result<(), String> {
         let postfix = postfix_for("\"hello\"+\"world\"");
 
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(
             formula_result,
             [Operand::Primitive(Value::String("helloworld".to_string()))]
@@ -88,12 +115,10 @@ mod tests {
     #[test]
     fn interpreter_succeeds_substracting() -> Result<(), String> {
         let postfix = postfix_for("2-1");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(
             formula_result,
-            [Operand::Primitive(Value::Number(
-                Number::from_f64(1.0).unwrap()
-            ))]
+            [Operand::Primitive(Value::Number(Number::from(1)))]
         );
         Ok(())
     }
@@ -101,7 +126,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_comparing_numbers_greater() -> Result<(), String> {
         let postfix = postfix_for("2>1");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(true))]);
         Ok(())
     }
@@ -109,7 +134,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_comparing_numbers_less() -> Result<(), String> {
         let postfix = postfix_for("20<1");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(false))]);
         Ok(())
     }
@@ -117,7 +142,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_comparing_non_eq_numbers() -> Result<(), String> {
         let postfix = postfix_for("20!=20");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(false))]);
         Ok(())
     }
@@ -125,7 +150,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_comparing_numbers_less_eq() -> Result<(), String> {
         let postfix = postfix_for("20<=20");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(true))]);
         Ok(())
     }
@@ -133,7 +158,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_comparing_numbers_ne() -> Result<(), String> {
         let postfix = postfix_for("20!=20");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(false))]);
         Ok(())
     }
@@ -141,12 +166,10 @@ mod tests {
     #[test]
     fn interpreter_succeeds_multiply() -> Result<(), String> {
         let postfix = postfix_for("20*2");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(
             formula_result,
-            [Operand::Primitive(Value::Number(
-                Number::from_f64(40.0).unwrap()
-            ))]
+            [Operand::Primitive(Value::Number(Number::from(40)))]
         );
         Ok(())
     }
@@ -154,12 +177,10 @@ mod tests {
     #[test]
     fn interpreter_succeeds_divide() -> Result<(), String> {
         let postfix = postfix_for("20/2");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(
             formula_result,
-            [Operand::Primitive(Value::Number(
-                Number::from_f64(10.0).unwrap()
-            ))]
+            [Operand::Primitive(Value::Number(Number::from(10)))]
         );
         Ok(())
     }
@@ -167,7 +188,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_divide_by_zero() -> Result<(), String> {
         let postfix = postfix_for("20/0");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Null)]);
         Ok(())
     }
@@ -175,7 +196,7 @@ mod tests {
     #[test]
     fn interpreter_succeeds_equality_check() -> Result<(), String> {
         let postfix = postfix_for("2+1=4-1");
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix?, &Value::Null)?;
         assert_eq!(formula_result, [Operand::Primitive(Value::Bool(true))]);
         Ok(())
     }
@@ -183,14 +204,252 @@ mod tests {
     #[test]
     fn interpreter_succeeds_sum_with_variable() -> Result<(), String> {
         let postfix = postfix_for("2+extraValue");
+        let context = serde_json::json!({ "extraValue": 2 });
+
+        let formula_result = interpret(&postfix?, &context)?;
+        assert_eq!(
+            formula_result,
+            [Operand::Primitive(Value::Number(Number::from(4)))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_number_functions() -> Result<(), String> {
+        let cases = vec![
+            ("abs(0-2.5)", serde_json::json!(2.5)),
+            ("floor(2.7)", serde_json::json!(2)),
+            ("ceiling(2.1)", serde_json::json!(3)),
+            ("round(2.5)", serde_json::json!(3)),
+            ("round(2.345, 2)", serde_json::json!(2.35)),
+            ("min(3, 1, 2)", serde_json::json!(1)),
+            ("max(3, 1, 2) * 2", serde_json::json!(6)),
+            ("sum(1, 2, 3)", serde_json::json!(6)),
+            ("sum(1, 2.5)", serde_json::json!(3.5)),
+            ("count(1, 2, 3)", serde_json::json!(3)),
+            ("string length(\"hello\")", serde_json::json!(5)),
+        ];
+
+        for (formula, expected) in cases {
+            assert_eq!(
+                eval(formula)?,
+                [Operand::Primitive(expected)],
+                "{}",
+                formula
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_keeps_large_integers() -> Result<(), String> {
+        let context: Value =
+            serde_json::from_str(r#"{ "id": 9007199254740993 }"#).map_err(|e| e.to_string())?;
 
-        let formula_result = interpret(&postfix?);
+        let formula_result = interpret(&postfix_for("id + 1")?, &context)?;
         assert_eq!(
             formula_result,
-            [Operand::Primitive(Value::Number(
-                Number::from_f64(4.0).unwrap()
-            ))]
+            [Operand::Primitive(Value::Number(Number::from(
+                9007199254740994u64
+            )))]
         );
+
+        let formula_result = interpret(&postfix_for("id = 9007199254740992")?, &context)?;
+        assert_eq!(formula_result, [Operand::Primitive(Value::Bool(false))]);
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_string_functions() -> Result<(), String> {
+        let cases = vec![
+            ("upper case(\"Fall\")", Value::String("FALL".to_string())),
+            ("lower case(\"Fall\")", Value::String("fall".to_string())),
+            (
+                "substring(\"foobar\", 3)",
+                Value::String("obar".to_string()),
+            ),
+            (
+                "substring(\"foobar\", 3, 3)",
+                Value::String("oba".to_string()),
+            ),
+            (
+                "substring(\"foobar\", 0-2, 1)",
+                Value::String("a".to_string()),
+            ),
+            (
+                "substring(\"abc\", 2, 18446744073709551615)",
+                Value::String("bc".to_string()),
+            ),
+            ("contains(\"foobar\", \"ob\")", Value::Bool(true)),
+            ("starts with(\"foobar\", \"fo\")", Value::Bool(true)),
+            ("ends with(\"foobar\", \"fo\")", Value::Bool(false)),
+            ("upper case(10)", Value::Null),
+        ];
+
+        for (formula, expected) in cases {
+            assert_eq!(
+                eval(formula)?,
+                [Operand::Primitive(expected)],
+                "{}",
+                formula
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_with_booleans_lists_and_contexts() -> Result<(), String> {
+        let context = serde_json::json!({
+            "vegetarian": true,
+            "allergies": ["nuts", 2],
+            "address": { "city": "Riga" },
+            "copy": { "city": "Riga" }
+        });
+        let cases = vec![
+            ("vegetarian = true", true),
+            ("vegetarian != false", true),
+            ("list contains(allergies, \"nuts\")", true),
+            ("list contains(allergies, 2.0)", true),
+            ("list contains(allergies, \"milk\")", false),
+            ("count(allergies) = 2", true),
+            ("address = copy", true),
+            ("address.city = \"Riga\"", true),
+            // no order between lists, contexts or mixed kinds
+            ("allergies < allergies", false),
+            ("address >= copy", false),
+            ("1 <= \"1\"", false),
+            ("\"apple\" < \"banana\"", true),
+        ];
+
+        for (formula, expected) in cases {
+            assert_eq!(
+                interpret(&postfix_for(formula)?, &context)?,
+                [Operand::Primitive(Value::Bool(expected))],
+                "{}",
+                formula
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_functions_with_context() -> Result<(), String> {
+        let postfix = postfix_for("max(price, 2) + string length(customer.name)");
+        let context = serde_json::json!({ "price": 2, "customer": { "name": "Ann" } });
+
+        let formula_result = interpret(&postfix?, &context)?;
+        assert_eq!(
+            formula_result,
+            [Operand::Primitive(Value::Number(Number::from(5)))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_fails_function_arity() {
+        assert!(eval("abs(1, 2)").is_err());
+        assert!(eval("substring(\"a\")").is_err());
+    }
+
+    #[test]
+    fn interpreter_succeeds_temporal_expressions() -> Result<(), String> {
+        let cases = vec![
+            "date(\"2024-01-31\") + duration(\"P1M\") = date(\"2024-02-29\")",
+            "date(\"2024-03-01\") - date(\"2024-02-01\") = duration(\"P29D\")",
+            "date and time(\"2024-01-31T10:00:00Z\") > date and time(\"2024-01-31T11:00:00+02:00\")",
+            "date(date and time(\"2024-01-31T10:00:00\")) = date(2024, 1, 31)",
+            "time(\"23:30:00\") + duration(\"PT1H\") = time(\"00:30:00\")",
+            "duration(\"PT1H\") * 2 = duration(\"PT120M\")",
+            "date(\"2024-01-31\") < date(deadline)",
+        ];
+        let context = serde_json::json!({ "deadline": "2024-02-01" });
+
+        for formula in cases {
+            let formula_result = interpret(&postfix_for(formula)?, &context)?;
+            assert_eq!(
+                formula_result,
+                [Operand::Primitive(Value::Bool(true))],
+                "{}",
+                formula
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_fails_comparing_different_temporals() -> Result<(), String> {
+        let cases = vec![
+            "date(\"2024-01-31\") <= time(\"10:00:00\")",
+            "date(\"2024-01-31\") >= time(\"10:00:00\")",
+            "duration(\"P1M\") <= duration(\"P30D\")",
+            "duration(\"P1M\") >= duration(\"P30D\")",
+        ];
+
+        for formula in cases {
+            let formula_result = interpret(&postfix_for(formula)?, &Value::Null)?;
+            assert_eq!(
+                formula_result,
+                [Operand::Primitive(Value::Bool(false))],
+                "{}",
+                formula
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_fails_invalid_temporal_literal() -> Result<(), String> {
+        assert!(eval("date(\"2024-13-01\")").is_err());
+        assert!(eval("time(\"10:00:00.éé\")").is_err());
+        assert!(eval("duration(\"P999999999999999999D\")").is_err());
+        assert_eq!(
+            eval("date(999999999999999999, 1, 1)")?,
+            [Operand::Primitive(Value::Null)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_decimal_mode() -> Result<(), String> {
+        let cases = vec![
+            ("0.1 + 0.2 = 0.3", true),
+            ("price * quantity = 0.3", true),
+            ("9007199254740993 + 1 = 9007199254740994", true),
+            ("1 / 3 * 3 < 1", true),
+            ("round(2.345, 2) = 2.35", true),
+            ("sum(0.1, 0.2, price) = 0.4", true),
+        ];
+        let context = serde_json::json!({ "price": 0.1, "quantity": 3 });
+
+        for (formula, expected) in cases {
+            let mut tokenizer = Tokenizer::with_number_mode(formula, NumberMode::Decimal);
+            tokenizer.parse()?;
+            let postfix = tokenizer.to_postfix()?;
+
+            let formula_result = interpret_with_mode(&postfix, &context, NumberMode::Decimal)?;
+            assert_eq!(
+                formula_result,
+                [Operand::Primitive(Value::Bool(expected))],
+                "{}",
+                formula
+            );
+        }
+
+        assert_eq!(
+            eval("0.1 + 0.2 = 0.3")?,
+            [Operand::Primitive(Value::Bool(false))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_succeeds_decimal_division_by_zero() -> Result<(), String> {
+        let mut tokenizer = Tokenizer::with_number_mode("1 / 0", NumberMode::Decimal);
+        tokenizer.parse()?;
+
+        let formula_result =
+            interpret_with_mode(&tokenizer.to_postfix()?, &Value::Null, NumberMode::Decimal)?;
+        assert_eq!(formula_result, [Operand::Primitive(Value::Null)]);
         Ok(())
     }
 }
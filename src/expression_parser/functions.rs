@@ -0,0 +1,377 @@
+use serde_json::{Number, Value};
+
+use super::decimal::Decimal;
+use super::number;
+use super::operand::Operand;
+use super::temporal::Temporal;
+
+/*
+    Built-in function library, names and semantics follow DMN FEEL:
+    - wrong argument types evaluate to null, wrong argument count is an error
+    - list functions accept either a single list or the items as arguments
+*/
+
+const FUNCTION_NAMES: [&str; 20] = [
+    "abs",
+    "floor",
+    "ceiling",
+    "round",
+    "min",
+    "max",
+    "sum",
+    "count",
+    "string length",
+    "upper case",
+    "lower case",
+    "contains",
+    "starts with",
+    "ends with",
+    "substring",
+    "list contains",
+    "date",
+    "time",
+    "date and time",
+    "duration",
+];
+
+pub fn is_function_name(name: &str) -> bool {
+    FUNCTION_NAMES.contains(&name)
+}
+
+/// True when `name` is the first word(s) of a multi-word function name, e.g. "string"
+pub fn is_function_name_prefix(name: &str) -> bool {
+    FUNCTION_NAMES
+        .iter()
+        .any(|f| f.len() > name.len() && f.starts_with(name) && f[name.len()..].starts_with(' '))
+}
+
+pub fn call(name: &str, args: Vec<Operand>) -> Result<Operand, String> {
+    match name {
+        "abs" => {
+            expect_args(name, &args, 1, 1)?;
+            let abs = |n: &Number| match n.as_i64() {
+                Some(i) => i.checked_abs().map(Number::from),
+                None if n.is_u64() => Some(n.clone()),
+                None => number::from_f64(n.as_f64()?.abs()),
+            };
+            Ok(number_fn(&args[0], abs, Decimal::abs))
+        }
+        "floor" => {
+            expect_args(name, &args, 1, 1)?;
+            Ok(number_fn(
+                &args[0],
+                |n| integral(n, f64::floor),
+                Decimal::floor,
+            ))
+        }
+        "ceiling" => {
+            expect_args(name, &args, 1, 1)?;
+            Ok(number_fn(
+                &args[0],
+                |n| integral(n, f64::ceil),
+                Decimal::ceiling,
+            ))
+        }
+        "round" => {
+            expect_args(name, &args, 1, 2)?;
+            let scale = match args.get(1) {
+                Some(s) => match as_f64(s) {
+                    Some(s) => s as i32,
+                    None => return Ok(null()),
+                },
+                None => 0,
+            };
+            let factor = 10f64.powi(scale);
+            let round = |n: &Number| {
+                if scale >= 0 && !n.is_f64() {
+                    return Some(n.clone());
+                }
+                let rounded = (n.as_f64()? * factor).round() / factor;
+                if scale <= 0 {
+                    return number::from_integral_f64(rounded);
+                }
+                number::from_f64(rounded)
+            };
+            Ok(number_fn(&args[0], round, |d| d.round(scale as i64)))
+        }
+        "min" | "max" | "sum" => {
+            let items = list_items(name, args)?;
+
+            if items.iter().any(|i| matches!(i, Operand::Decimal(_))) {
+                let mut decimals = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    match as_decimal(item) {
+                        Some(d) => decimals.push(d),
+                        None => return Ok(null()),
+                    }
+                }
+
+                let res = match name {
+                    "min" => decimals.into_iter().min(),
+                    "max" => decimals.into_iter().max(),
+                    _ => decimals.into_iter().reduce(|a, b| a.add(&b)),
+                };
+                return Ok(res.map(Operand::Decimal).unwrap_or_else(null));
+            }
+
+            let mut numbers = Vec::with_capacity(items.len());
+            for item in items.into_iter() {
+                match item {
+                    Operand::Primitive(Value::Number(n)) => numbers.push(n),
+                    _ => return Ok(null()),
+                }
+            }
+
+            let res = match name {
+                "min" => numbers.into_iter().min_by(number::cmp),
+                "max" => numbers.into_iter().max_by(number::cmp),
+                _ => numbers
+                    .into_iter()
+                    .map(Some)
+                    .reduce(|a, b| number::add(&a?, &b?))
+                    .flatten(),
+            };
+            Ok(res
+                .map(|n| Operand::Primitive(Value::Number(n)))
+                .unwrap_or_else(null))
+        }
+        "count" => {
+            let items = list_items(name, args)?;
+            Ok(Operand::Primitive(Value::Number(Number::from(items.len()))))
+        }
+        "string length" => {
+            expect_args(name, &args, 1, 1)?;
+            Ok(string_fn(&args[0], |s| {
+                Operand::Primitive(Value::Number(Number::from(s.chars().count())))
+            }))
+        }
+        "upper case" => {
+            expect_args(name, &args, 1, 1)?;
+            Ok(string_fn(&args[0], |s| string(s.to_uppercase())))
+        }
+        "lower case" => {
+            expect_args(name, &args, 1, 1)?;
+            Ok(string_fn(&args[0], |s| string(s.to_lowercase())))
+        }
+        "contains" | "starts with" | "ends with" => {
+            expect_args(name, &args, 2, 2)?;
+            match (as_str(&args[0]), as_str(&args[1])) {
+                (Some(s), Some(m)) => {
+                    let res = match name {
+                        "contains" => s.contains(m),
+                        "starts with" => s.starts_with(m),
+                        _ => s.ends_with(m),
+                    };
+                    Ok(Operand::Primitive(Value::Bool(res)))
+                }
+                _ => Ok(null()),
+            }
+        }
+        "substring" => {
+            expect_args(name, &args, 2, 3)?;
+            let length = match args.get(2) {
+                Some(l) => match as_f64(l) {
+                    Some(l) if l >= 0.0 => Some(l as usize),
+                    _ => return Ok(null()),
+                },
+                None => None,
+            };
+            match (as_str(&args[0]), as_f64(&args[1])) {
+                (Some(s), Some(start)) => Ok(substring(s, start as i64, length)),
+                _ => Ok(null()),
+            }
+        }
+        "list contains" => {
+            expect_args(name, &args, 2, 2)?;
+            match &args[0] {
+                Operand::Primitive(Value::Array(items)) => {
+                    let found = items
+                        .iter()
+                        .any(|item| Operand::Primitive(item.clone()).equals(&args[1]));
+                    Ok(Operand::Primitive(Value::Bool(found)))
+                }
+                _ => Ok(null()),
+            }
+        }
+        "date" => {
+            expect_args(name, &args, 1, 3)?;
+            if args.len() == 3 {
+                return match (as_f64(&args[0]), as_f64(&args[1]), as_f64(&args[2])) {
+                    (Some(y), Some(m), Some(d)) => {
+                        Ok(temporal(Temporal::from_ymd(y as i64, m as i64, d as i64)))
+                    }
+                    _ => Ok(null()),
+                };
+            }
+            expect_args(name, &args, 1, 1)?;
+            temporal_fn(&args[0], Temporal::parse_date, Temporal::date)
+        }
+        "time" => {
+            expect_args(name, &args, 1, 1)?;
+            temporal_fn(&args[0], Temporal::parse_time, Temporal::time)
+        }
+        "date and time" => {
+            expect_args(name, &args, 1, 2)?;
+            if let (Some(Operand::Temporal(d)), Some(Operand::Temporal(t))) =
+                (args.first(), args.get(1))
+            {
+                return Ok(temporal(Temporal::combine(d, t)));
+            }
+            expect_args(name, &args, 1, 1)?;
+            temporal_fn(&args[0], Temporal::parse_date_time, |t| match t {
+                Temporal::DateTime { .. } => Some(t.clone()),
+                _ => None,
+            })
+        }
+        "duration" => {
+            expect_args(name, &args, 1, 1)?;
+            temporal_fn(&args[0], Temporal::parse_duration, |t| match t {
+                Temporal::Duration { .. } => Some(t.clone()),
+                _ => None,
+            })
+        }
+        _ => Err(format!("unknown function \"{}\"", name)),
+    }
+}
+
+fn expect_args(name: &str, args: &[Operand], min: usize, max: usize) -> Result<(), String> {
+    if args.len() < min || args.len() > max {
+        if min == max {
+            return Err(format!(
+                "function \"{}\" expects {} argument(s), got {}",
+                name,
+                min,
+                args.len()
+            ));
+        }
+        return Err(format!(
+            "function \"{}\" expects {} to {} arguments, got {}",
+            name,
+            min,
+            max,
+            args.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Items of a list function call - `f([1, 2])` and `f(1, 2)` are the same
+fn list_items(name: &str, mut args: Vec<Operand>) -> Result<Vec<Operand>, String> {
+    if args.is_empty() {
+        return Err(format!("function \"{}\" expects at least 1 argument", name));
+    }
+
+    if let [Operand::Primitive(Value::Array(_))] = args.as_slice() {
+        if let Some(Operand::Primitive(Value::Array(items))) = args.pop() {
+            return Ok(items.into_iter().map(Operand::Primitive).collect());
+        }
+    }
+
+    Ok(args)
+}
+
+/// FEEL substring - `start` is 1 based, negative values count from the end
+fn substring(s: &str, start: i64, length: Option<usize>) -> Operand {
+    let chars: Vec<char> = s.chars().collect();
+    let len = chars.len() as i64;
+
+    let from = match start {
+        0 => return null(),
+        s if s > 0 => s - 1,
+        s => len + s,
+    };
+    if from < 0 || from > len {
+        return null();
+    }
+
+    let from = from as usize;
+    let to = match length {
+        Some(l) => from.saturating_add(l).min(chars.len()),
+        None => chars.len(),
+    };
+
+    string(chars[from..to].iter().collect())
+}
+
+fn as_f64(o: &Operand) -> Option<f64> {
+    match o {
+        Operand::Primitive(Value::Number(n)) => n.as_f64(),
+        Operand::Decimal(d) => Some(d.to_f64()),
+        _ => None,
+    }
+}
+
+fn as_decimal(o: &Operand) -> Option<Decimal> {
+    match o {
+        Operand::Primitive(Value::Number(n)) => Decimal::from_number(n),
+        Operand::Decimal(d) => Some(d.clone()),
+        _ => None,
+    }
+}
+
+fn as_str(o: &Operand) -> Option<&str> {
+    match o {
+        Operand::Primitive(Value::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+fn number_fn(
+    o: &Operand,
+    f: impl Fn(&Number) -> Option<Number>,
+    d: impl Fn(&Decimal) -> Decimal,
+) -> Operand {
+    match o {
+        Operand::Decimal(n) => Operand::Decimal(d(n)),
+        Operand::Primitive(Value::Number(n)) => match f(n) {
+            Some(n) => Operand::Primitive(Value::Number(n)),
+            None => null(),
+        },
+        _ => null(),
+    }
+}
+
+/// Integers are already integral, floats are rounded by `f` into an integer
+fn integral(n: &Number, f: impl Fn(f64) -> f64) -> Option<Number> {
+    if !n.is_f64() {
+        return Some(n.clone());
+    }
+
+    number::from_integral_f64(f(n.as_f64()?))
+}
+
+fn string_fn(o: &Operand, f: impl Fn(&str) -> Operand) -> Operand {
+    match as_str(o) {
+        Some(s) => f(s),
+        None => null(),
+    }
+}
+
+/// Parses a string argument, or converts an already temporal argument
+fn temporal_fn(
+    o: &Operand,
+    parse: impl Fn(&str) -> Result<Temporal, String>,
+    convert: impl Fn(&Temporal) -> Option<Temporal>,
+) -> Result<Operand, String> {
+    match o {
+        Operand::Primitive(Value::String(s)) => Ok(Operand::Temporal(parse(s)?)),
+        Operand::Temporal(t) => Ok(temporal(convert(t))),
+        _ => Ok(null()),
+    }
+}
+
+fn temporal(t: Option<Temporal>) -> Operand {
+    match t {
+        Some(t) => Operand::Temporal(t),
+        None => null(),
+    }
+}
+
+fn string(s: String) -> Operand {
+    Operand::Primitive(Value::String(s))
+}
+
+fn null() -> Operand {
+    Operand::Primitive(Value::Null)
+}
@@ -1,3 +1,7 @@
+pub mod decimal;
 pub mod executor;
+pub mod functions;
+pub mod number;
 pub mod operand;
+pub mod temporal;
 pub mod tokenizer;
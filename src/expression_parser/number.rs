@@ -0,0 +1,111 @@
+use serde_json::Number;
+use std::cmp::Ordering;
+
+/*
+    Arithmetic on JSON numbers, shared by expressions and JsonLogic.
+    Integers stay integers (i64 / u64) while the result fits and is exact,
+    anything else falls back to f64. NaN and infinity become None.
+*/
+
+pub fn add(n1: &Number, n2: &Number) -> Option<Number> {
+    integer_op(n1, n2, i128::checked_add).or_else(|| from_f64(n1.as_f64()? + n2.as_f64()?))
+}
+
+pub fn sub(n1: &Number, n2: &Number) -> Option<Number> {
+    integer_op(n1, n2, i128::checked_sub).or_else(|| from_f64(n1.as_f64()? - n2.as_f64()?))
+}
+
+pub fn mul(n1: &Number, n2: &Number) -> Option<Number> {
+    integer_op(n1, n2, i128::checked_mul).or_else(|| from_f64(n1.as_f64()? * n2.as_f64()?))
+}
+
+/// None on division by zero, integers only stay integers when the division is exact
+pub fn div(n1: &Number, n2: &Number) -> Option<Number> {
+    let divisor = n2.as_f64()?;
+    if divisor == 0.0 {
+        return None;
+    }
+
+    let exact = |a: i128, b: i128| match a % b {
+        0 => Some(a / b),
+        _ => None,
+    };
+    integer_op(n1, n2, exact).or_else(|| from_f64(n1.as_f64()? / divisor))
+}
+
+pub fn cmp(n1: &Number, n2: &Number) -> Ordering {
+    if let (Some(i1), Some(i2)) = (as_i128(n1), as_i128(n2)) {
+        return i1.cmp(&i2);
+    }
+
+    let (f1, f2) = (n1.as_f64().unwrap_or(0.0), n2.as_f64().unwrap_or(0.0));
+    f1.partial_cmp(&f2).unwrap_or(Ordering::Equal)
+}
+
+pub fn from_f64(n: f64) -> Option<Number> {
+    Number::from_f64(n)
+}
+
+/// Integer when `n` has no fractional part and fits i64, e.g. the result of floor()
+pub fn from_integral_f64(n: f64) -> Option<Number> {
+    if n.fract() == 0.0 && n >= i64::MIN as f64 && n <= i64::MAX as f64 {
+        return Some(Number::from(n as i64));
+    }
+
+    from_f64(n)
+}
+
+fn integer_op(n1: &Number, n2: &Number, op: impl Fn(i128, i128) -> Option<i128>) -> Option<Number> {
+    let res = op(as_i128(n1)?, as_i128(n2)?)?;
+
+    if let Ok(i) = i64::try_from(res) {
+        return Some(Number::from(i));
+    }
+    u64::try_from(res).ok().map(Number::from)
+}
+
+fn as_i128(n: &Number) -> Option<i128> {
+    n.as_i64()
+        .map(i128::from)
+        .or_else(|| n.as_u64().map(i128::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn n(text: &str) -> Number {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn keeps_integers_exact() {
+        assert_eq!(add(&n("8"), &n("1")), Some(n("9")));
+        assert_eq!(
+            add(&n("9007199254740993"), &n("1")),
+            Some(n("9007199254740994"))
+        );
+        assert_eq!(
+            mul(&n("18446744073709551615"), &n("1")),
+            Some(n("18446744073709551615"))
+        );
+        assert_eq!(
+            sub(&n("1"), &n("18446744073709551615")),
+            Some(n("-1.8446744073709552e19"))
+        );
+        assert_eq!(div(&n("20"), &n("2")), Some(n("10")));
+        assert_eq!(div(&n("1"), &n("2")), Some(n("0.5")));
+        assert_eq!(div(&n("1"), &n("0")), None);
+        assert_eq!(add(&n("1"), &n("1.5")), Some(n("2.5")));
+    }
+
+    #[test]
+    fn compares_numbers() {
+        assert_eq!(
+            cmp(&n("9007199254740993"), &n("9007199254740992")),
+            Ordering::Greater
+        );
+        assert_eq!(cmp(&n("8"), &n("8.0")), Ordering::Equal);
+        assert_eq!(cmp(&n("-1"), &n("18446744073709551615")), Ordering::Less);
+    }
+}
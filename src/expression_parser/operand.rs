@@ -1,9 +1,15 @@
+#![allow(clippy::just_underscores_and_digits)]
+
 use serde_json::{Number, Value};
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Sub;
 
+use super::decimal::Decimal;
+use super::number;
+use super::temporal::Temporal;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Operator {
     Plus,
@@ -18,30 +24,58 @@ pub enum Operator {
     NE,
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub enum Operand {
     Primitive(Value),
+    Temporal(Temporal),
+    Decimal(Decimal),
     Variable(String),
     OperatorToken(Operator),
+    /// Built-in function call, the argument count is filled in by `Tokenizer::to_postfix`
+    Function(String, usize),
     OpenParen,
     CloseParen,
+    Comma,
+}
+
+impl Operand {
+    /// Value equality, numbers compare by value - 8 equals 8.0 and decimal 8,
+    /// lists are equal when their items are
+    pub fn equals(&self, other: &Operand) -> bool {
+        self == other
+    }
+
+    /// JSON form of a result - temporals as ISO strings, decimals as numbers
+    pub fn to_value(&self) -> Value {
+        match self {
+            Operand::Primitive(v) => v.clone(),
+            Operand::Temporal(t) => Value::String(t.to_string()),
+            Operand::Decimal(d) => match d.to_number() {
+                Some(n) => Value::Number(n),
+                None => Value::String(d.to_string()),
+            },
+            _ => Value::Null,
+        }
+    }
 }
 
 impl Add for Operand {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
+        if let Some((d1, d2)) = decimal_pair(&self, &other) {
+            return Operand::Decimal(d1.add(&d2));
+        }
+
         match (self, other) {
             (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
-                (Value::Number(n1), Value::Number(n2)) => {
-                    let res = n1.as_f64().unwrap_or(0.0) + n2.as_f64().unwrap_or(0.0);
-                    Operand::Primitive(Value::Number(Number::from_f64(res).unwrap()))
-                }
+                (Value::Number(n1), Value::Number(n2)) => number_or_null(number::add(&n1, &n2)),
                 (Value::String(s1), Value::String(s2)) => {
                     Operand::Primitive(Value::String(format!("{}{}", s1, s2)))
                 }
                 (_, _1) => Operand::Primitive(Value::Null),
             },
+            (Operand::Temporal(t1), Operand::Temporal(t2)) => temporal_or_null(t1.add(&t2)),
             (_, _1) => Operand::Primitive(Value::Null),
         }
     }
@@ -51,14 +85,16 @@ impl Sub for Operand {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
+        if let Some((d1, d2)) = decimal_pair(&self, &other) {
+            return Operand::Decimal(d1.sub(&d2));
+        }
+
         match (self, other) {
             (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
-                (Value::Number(n1), Value::Number(n2)) => {
-                    let res = n1.as_f64().unwrap_or(0.0) - n2.as_f64().unwrap_or(0.0);
-                    Operand::Primitive(Value::Number(Number::from_f64(res).unwrap()))
-                }
+                (Value::Number(n1), Value::Number(n2)) => number_or_null(number::sub(&n1, &n2)),
                 (_, _1) => Operand::Primitive(Value::Null),
             },
+            (Operand::Temporal(t1), Operand::Temporal(t2)) => temporal_or_null(t1.sub(&t2)),
             (_, _1) => Operand::Primitive(Value::Null),
         }
     }
@@ -68,14 +104,19 @@ impl Mul for Operand {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self {
+        if let Some((d1, d2)) = decimal_pair(&self, &other) {
+            return Operand::Decimal(d1.mul(&d2));
+        }
+
         match (self, other) {
             (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
-                (Value::Number(n1), Value::Number(n2)) => {
-                    let res = n1.as_f64().unwrap_or(0.0) * n2.as_f64().unwrap_or(0.0);
-                    Operand::Primitive(Value::Number(Number::from_f64(res).unwrap()))
-                }
+                (Value::Number(n1), Value::Number(n2)) => number_or_null(number::mul(&n1, &n2)),
                 (_, _1) => Operand::Primitive(Value::Null),
             },
+            (Operand::Temporal(t), Operand::Primitive(Value::Number(n)))
+            | (Operand::Primitive(Value::Number(n)), Operand::Temporal(t)) => {
+                temporal_or_null(t.scale(n.as_f64().unwrap_or(0.0)))
+            }
             (_, _1) => Operand::Primitive(Value::Null),
         }
     }
@@ -85,51 +126,139 @@ impl Div for Operand {
     type Output = Self;
 
     fn div(self, other: Self) -> Self {
+        if let Some((d1, d2)) = decimal_pair(&self, &other) {
+            return match d1.div(&d2) {
+                Some(d) => Operand::Decimal(d),
+                None => Operand::Primitive(Value::Null),
+            };
+        }
+
         match (self, other) {
             (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
-                (Value::Number(n1), Value::Number(n2)) => {
-                    let n2_unwrapped = n2.as_f64().unwrap_or(0.0);
-                    if n2_unwrapped == 0.0 {
-                        return Operand::Primitive(Value::Null);
-                    }
-                    let res = n1.as_f64().unwrap_or(0.0) / n2_unwrapped;
-                    Operand::Primitive(Value::Number(Number::from_f64(res).unwrap()))
-                }
+                (Value::Number(n1), Value::Number(n2)) => number_or_null(number::div(&n1, &n2)),
                 (_, _1) => Operand::Primitive(Value::Null),
             },
+            (Operand::Temporal(t), Operand::Primitive(Value::Number(n))) => {
+                let n = n.as_f64().unwrap_or(0.0);
+                if n == 0.0 {
+                    return Operand::Primitive(Value::Null);
+                }
+                temporal_or_null(t.scale(1.0 / n))
+            }
             (_, _1) => Operand::Primitive(Value::Null),
         }
     }
 }
 
-impl std::cmp::Ord for Operand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+/// Same value equality as `Operand::equals`, so it agrees with `partial_cmp` on numbers
+impl PartialEq for Operand {
+    fn eq(&self, other: &Self) -> bool {
+        if let Some((d1, d2)) = decimal_pair(self, other) {
+            return d1 == d2;
+        }
+
         match (self, other) {
-            (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
-                (Value::Number(n1_base), Value::Number(n2_base)) => {
-                    let n1 = n1_base.as_f64().unwrap_or(0.0);
-                    let n2 = n2_base.as_f64().unwrap_or(0.0);
-                    // (Operand::Number(n1), Operand::Number(n2)) => {
-                    if n1 > n2 {
-                        return std::cmp::Ordering::Greater;
-                    }
-                    if n1 < n2 {
-                        return std::cmp::Ordering::Less;
-                    }
-
-                    return std::cmp::Ordering::Equal;
-                }
-                (_, _1) => std::cmp::Ordering::Equal,
-            },
-            (_, _1) => std::cmp::Ordering::Equal,
+            (Operand::Primitive(p1), Operand::Primitive(p2)) => values_equal(p1, p2),
+            (Operand::Temporal(t1), Operand::Temporal(t2)) => t1 == t2,
+            (Operand::Variable(v1), Operand::Variable(v2)) => v1 == v2,
+            (Operand::OperatorToken(o1), Operand::OperatorToken(o2)) => o1 == o2,
+            (Operand::Function(f1, a1), Operand::Function(f2, a2)) => f1 == f2 && a1 == a2,
+            (Operand::OpenParen, Operand::OpenParen)
+            | (Operand::CloseParen, Operand::CloseParen)
+            | (Operand::Comma, Operand::Comma) => true,
+            (_, _) => false,
         }
     }
 }
 
+fn values_equal(v1: &Value, v2: &Value) -> bool {
+    match (v1, v2) {
+        (Value::Number(n1), Value::Number(n2)) => number::cmp(n1, n2) == std::cmp::Ordering::Equal,
+        (Value::Array(a1), Value::Array(a2)) => {
+            a1.len() == a2.len()
+                && a1
+                    .iter()
+                    .zip(a2.iter())
+                    .all(|(i1, i2)| values_equal(i1, i2))
+        }
+        (_, _) => v1 == v2,
+    }
+}
+
+/// Numbers, decimals, strings, booleans and temporals of the same kind are ordered,
+/// anything else - null, lists, contexts, mixed kinds - has no order and compares false
 impl PartialOrd for Operand {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+        if let Some((d1, d2)) = decimal_pair(self, other) {
+            return Some(d1.cmp(&d2));
+        }
+
+        match (self, other) {
+            (Operand::Primitive(p1), Operand::Primitive(p2)) => match (p1, p2) {
+                (Value::Number(n1), Value::Number(n2)) => Some(number::cmp(n1, n2)),
+                (Value::String(s1), Value::String(s2)) => Some(s1.cmp(s2)),
+                (Value::Bool(b1), Value::Bool(b2)) => Some(b1.cmp(b2)),
+                (_, _) => None,
+            },
+            (Operand::Temporal(t1), Operand::Temporal(t2)) => t1.partial_cmp(t2),
+            (_, _) => None,
+        }
     }
 }
 
-impl Eq for Operand {}
+fn number_or_null(n: Option<Number>) -> Operand {
+    match n {
+        Some(n) => Operand::Primitive(Value::Number(n)),
+        None => Operand::Primitive(Value::Null),
+    }
+}
+
+fn temporal_or_null(t: Option<Temporal>) -> Operand {
+    match t {
+        Some(t) => Operand::Temporal(t),
+        None => Operand::Primitive(Value::Null),
+    }
+}
+
+/// Both operands as decimals, when one is a decimal and the other one a decimal or a number
+fn decimal_pair(l: &Operand, r: &Operand) -> Option<(Decimal, Decimal)> {
+    let as_decimal = |o: &Operand| match o {
+        Operand::Decimal(d) => Some(d.clone()),
+        Operand::Primitive(Value::Number(n)) => Decimal::from_number(n),
+        _ => None,
+    };
+
+    match (l, r) {
+        (Operand::Decimal(_), _) | (_, Operand::Decimal(_)) => {
+            Some((as_decimal(l)?, as_decimal(r)?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn equality_agrees_with_ordering() {
+        let eight = Operand::Primitive(Value::Number(Number::from(8)));
+        let cases = vec![
+            Operand::Primitive(Value::Number(Number::from_f64(8.0).unwrap())),
+            Operand::Decimal(Decimal::parse("8.00").unwrap()),
+        ];
+
+        for other in cases {
+            assert_eq!(eight.partial_cmp(&other), Some(Ordering::Equal));
+            assert_eq!(eight, other);
+            assert!(eight.equals(&other));
+        }
+
+        assert_eq!(
+            Operand::Primitive(serde_json::json!([8, "a"])),
+            Operand::Primitive(serde_json::json!([8.0, "a"]))
+        );
+        assert_ne!(eight, Operand::Primitive(Value::String("8".to_string())));
+    }
+}
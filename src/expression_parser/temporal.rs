@@ -0,0 +1,667 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/*
+    FEEL temporal values
+    - date: days since 1970-01-01
+    - time / date and time: wall clock milliseconds with an optional UTC offset,
+      values without an offset are compared as if they were UTC
+    - duration: months (years and months) plus milliseconds (days and time),
+      both carry the sign
+*/
+
+const MILLIS_PER_SECOND: i64 = 1_000;
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+#[derive(Debug, Clone)]
+pub enum Temporal {
+    Date(i64),
+    Time { millis: i64, offset: Option<i32> },
+    DateTime { millis: i64, offset: Option<i32> },
+    Duration { months: i64, millis: i64 },
+}
+
+impl Temporal {
+    pub fn parse_date(s: &str) -> Result<Temporal, String> {
+        parse_ymd(s)
+            .and_then(|(y, m, d)| Temporal::from_ymd(y, m, d))
+            .ok_or_else(|| format!("invalid date \"{}\"", s))
+    }
+
+    pub fn parse_time(s: &str) -> Result<Temporal, String> {
+        let (millis, offset) = parse_hms(s).ok_or_else(|| format!("invalid time \"{}\"", s))?;
+        Ok(Temporal::Time { millis, offset })
+    }
+
+    /// Accepts "2024-01-31T10:00:00+02:00" and a plain date, which means midnight
+    pub fn parse_date_time(s: &str) -> Result<Temporal, String> {
+        let err = || format!("invalid date and time \"{}\"", s);
+
+        let (date_part, time_part) = match s.split_once('T') {
+            Some((d, t)) => (d, Some(t)),
+            None => (s, None),
+        };
+        let (y, m, d) = parse_ymd(date_part).ok_or_else(err)?;
+        let (time_millis, offset) = match time_part {
+            Some(t) => parse_hms(t).ok_or_else(err)?,
+            None => (0, None),
+        };
+
+        let millis = days_from_civil(y, m, d)
+            .and_then(day_millis)
+            .and_then(|millis| millis.checked_add(time_millis))
+            .ok_or_else(err)?;
+
+        Ok(Temporal::DateTime { millis, offset })
+    }
+
+    /// ISO 8601 duration, e.g. "P1Y2M", "P3DT4H30M", "-PT1.5S"
+    pub fn parse_duration(s: &str) -> Result<Temporal, String> {
+        let err = || format!("invalid duration \"{}\"", s);
+
+        let (negative, rest) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let rest = rest.strip_prefix('P').ok_or_else(err)?;
+        let (date_part, time_part) = match rest.split_once('T') {
+            Some((d, t)) if !t.is_empty() => (d, Some(t)),
+            Some(_) => return Err(err()),
+            None => (rest, None),
+        };
+        if date_part.is_empty() && time_part.is_none() {
+            return Err(err());
+        }
+
+        let mut months: i64 = 0;
+        let mut millis: i64 = 0;
+
+        for (amount, unit) in duration_parts(date_part).ok_or_else(err)? {
+            if amount.fract() != 0.0 || amount >= i64::MAX as f64 {
+                return Err(err());
+            }
+            let amount = amount as i64;
+            let (unit_months, unit_millis) = match unit {
+                'Y' => (12, 0),
+                'M' => (1, 0),
+                'W' => (0, 7 * MILLIS_PER_DAY),
+                'D' => (0, MILLIS_PER_DAY),
+                _ => return Err(err()),
+            };
+            months = amount
+                .checked_mul(unit_months)
+                .and_then(|a| months.checked_add(a))
+                .ok_or_else(err)?;
+            millis = amount
+                .checked_mul(unit_millis)
+                .and_then(|a| millis.checked_add(a))
+                .ok_or_else(err)?;
+        }
+
+        for (amount, unit) in duration_parts(time_part.unwrap_or("")).ok_or_else(err)? {
+            let seconds = match unit {
+                'H' => amount * 3600.0,
+                'M' => amount * 60.0,
+                'S' => amount,
+                _ => return Err(err()),
+            };
+            let amount = (seconds * MILLIS_PER_SECOND as f64).round();
+            if amount >= i64::MAX as f64 {
+                return Err(err());
+            }
+            millis = millis.checked_add(amount as i64).ok_or_else(err)?;
+        }
+
+        if negative {
+            months = -months;
+            millis = -millis;
+        }
+
+        Ok(Temporal::Duration { months, millis })
+    }
+
+    pub fn from_ymd(y: i64, m: i64, d: i64) -> Option<Temporal> {
+        if !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+            return None;
+        }
+
+        // every date has to be representable as a date and time
+        let days = days_from_civil(y, m, d)?;
+        day_millis(days)?;
+        Some(Temporal::Date(days))
+    }
+
+    /// date and time(date, time)
+    pub fn combine(date: &Temporal, time: &Temporal) -> Option<Temporal> {
+        match (date, time) {
+            (Temporal::Date(days), Temporal::Time { millis, offset }) => Some(Temporal::DateTime {
+                millis: day_millis(*days)?.checked_add(*millis)?,
+                offset: *offset,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Date part of a date or date and time
+    pub fn date(&self) -> Option<Temporal> {
+        match self {
+            Temporal::Date(_) => Some(self.clone()),
+            Temporal::DateTime { millis, .. } => {
+                Some(Temporal::Date(millis.div_euclid(MILLIS_PER_DAY)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Time part of a time or date and time
+    pub fn time(&self) -> Option<Temporal> {
+        match self {
+            Temporal::Time { .. } => Some(self.clone()),
+            Temporal::DateTime { millis, offset } => Some(Temporal::Time {
+                millis: millis.rem_euclid(MILLIS_PER_DAY),
+                offset: *offset,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn add(&self, other: &Temporal) -> Option<Temporal> {
+        match (self, other) {
+            (_, Temporal::Duration { .. }) => self.shift(other.duration_parts()?, 1),
+            (Temporal::Duration { .. }, _) => other.shift(self.duration_parts()?, 1),
+            _ => None,
+        }
+    }
+
+    pub fn sub(&self, other: &Temporal) -> Option<Temporal> {
+        match (self, other) {
+            (_, Temporal::Duration { .. }) => self.shift(other.duration_parts()?, -1),
+            (Temporal::Date(d1), Temporal::Date(d2)) => Some(Temporal::Duration {
+                months: 0,
+                millis: day_millis(d1.checked_sub(*d2)?)?,
+            }),
+            (Temporal::Time { .. }, Temporal::Time { .. })
+            | (Temporal::DateTime { .. }, Temporal::DateTime { .. })
+            | (Temporal::DateTime { .. }, Temporal::Date(_))
+            | (Temporal::Date(_), Temporal::DateTime { .. }) => Some(Temporal::Duration {
+                months: 0,
+                millis: self.instant()?.checked_sub(other.instant()?)?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Scales a duration, months are rounded to whole months
+    pub fn scale(&self, factor: f64) -> Option<Temporal> {
+        match self {
+            Temporal::Duration { months, millis } if factor.is_finite() => {
+                let scaled = |value: i64| {
+                    let scaled = (value as f64 * factor).round();
+                    (scaled.abs() < i64::MAX as f64).then_some(scaled as i64)
+                };
+                Some(Temporal::Duration {
+                    months: scaled(*months)?,
+                    millis: scaled(*millis)?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn duration_parts(&self) -> Option<(i64, i64)> {
+        match self {
+            Temporal::Duration { months, millis } => Some((*months, *millis)),
+            _ => None,
+        }
+    }
+
+    fn shift(&self, (months, millis): (i64, i64), sign: i64) -> Option<Temporal> {
+        let (months, millis) = (months.checked_mul(sign)?, millis.checked_mul(sign)?);
+
+        match self {
+            Temporal::Date(days) => {
+                let shifted = day_millis(add_months(*days, months)?)?.checked_add(millis)?;
+                Some(Temporal::Date(shifted.div_euclid(MILLIS_PER_DAY)))
+            }
+            Temporal::DateTime {
+                millis: base,
+                offset,
+            } => {
+                let days = add_months(base.div_euclid(MILLIS_PER_DAY), months)?;
+                Some(Temporal::DateTime {
+                    millis: day_millis(days)?
+                        .checked_add(base.rem_euclid(MILLIS_PER_DAY))?
+                        .checked_add(millis)?,
+                    offset: *offset,
+                })
+            }
+            Temporal::Time {
+                millis: base,
+                offset,
+            } => Some(Temporal::Time {
+                millis: (base + millis.rem_euclid(MILLIS_PER_DAY)).rem_euclid(MILLIS_PER_DAY),
+                offset: *offset,
+            }),
+            Temporal::Duration {
+                months: base_months,
+                millis: base_millis,
+            } => Some(Temporal::Duration {
+                months: base_months.checked_add(months)?,
+                millis: base_millis.checked_add(millis)?,
+            }),
+        }
+    }
+
+    /// Milliseconds in UTC, dates are midnight
+    fn instant(&self) -> Option<i64> {
+        match self {
+            Temporal::Date(days) => day_millis(*days),
+            Temporal::Time { millis, offset } | Temporal::DateTime { millis, offset } => {
+                millis.checked_sub(offset.unwrap_or(0) as i64 * MILLIS_PER_SECOND)
+            }
+            Temporal::Duration { .. } => None,
+        }
+    }
+}
+
+impl PartialEq for Temporal {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Temporal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Temporal::Date(d1), Temporal::Date(d2)) => Some(d1.cmp(d2)),
+            (Temporal::Time { .. }, Temporal::Time { .. })
+            | (Temporal::DateTime { .. }, Temporal::DateTime { .. })
+            | (Temporal::DateTime { .. }, Temporal::Date(_))
+            | (Temporal::Date(_), Temporal::DateTime { .. }) => {
+                Some(self.instant()?.cmp(&other.instant()?))
+            }
+            (
+                Temporal::Duration {
+                    months: m1,
+                    millis: ms1,
+                },
+                Temporal::Duration {
+                    months: m2,
+                    millis: ms2,
+                },
+            ) => {
+                // years and months durations don't compare with days and time durations
+                if m1 == m2 {
+                    Some(ms1.cmp(ms2))
+                } else if ms1 == ms2 {
+                    Some(m1.cmp(m2))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Temporal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Temporal::Date(days) => write_date(f, *days),
+            Temporal::Time { millis, offset } => write_time(f, *millis, *offset),
+            Temporal::DateTime { millis, offset } => {
+                write_date(f, millis.div_euclid(MILLIS_PER_DAY))?;
+                write!(f, "T")?;
+                write_time(f, millis.rem_euclid(MILLIS_PER_DAY), *offset)
+            }
+            Temporal::Duration { months, millis } => {
+                if *months < 0 || *millis < 0 {
+                    write!(f, "-")?;
+                }
+                write!(f, "P")?;
+
+                let (months, millis) = (months.unsigned_abs(), millis.unsigned_abs());
+                let millis_per_day = MILLIS_PER_DAY as u64;
+                if months == 0 && millis == 0 {
+                    return write!(f, "T0S");
+                }
+                if months / 12 > 0 {
+                    write!(f, "{}Y", months / 12)?;
+                }
+                if months % 12 > 0 {
+                    write!(f, "{}M", months % 12)?;
+                }
+
+                let days = millis / millis_per_day;
+                let rest = millis % millis_per_day;
+                if days > 0 {
+                    write!(f, "{}D", days)?;
+                }
+                if rest > 0 {
+                    write!(f, "T")?;
+                    let (hours, minutes) = (rest / 3_600_000, rest % 3_600_000 / 60_000);
+                    let seconds = rest % 60_000;
+                    if hours > 0 {
+                        write!(f, "{}H", hours)?;
+                    }
+                    if minutes > 0 {
+                        write!(f, "{}M", minutes)?;
+                    }
+                    if !seconds.is_multiple_of(1000) {
+                        write!(f, "{}.{:03}S", seconds / 1000, seconds % 1000)?;
+                    } else if seconds > 0 {
+                        write!(f, "{}S", seconds / 1000)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_date(f: &mut fmt::Formatter, days: i64) -> fmt::Result {
+    let (y, m, d) = civil_from_days(days);
+    write!(f, "{:04}-{:02}-{:02}", y, m, d)
+}
+
+fn write_time(f: &mut fmt::Formatter, millis: i64, offset: Option<i32>) -> fmt::Result {
+    let seconds = millis / 1000;
+    write!(
+        f,
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )?;
+    if millis % 1000 > 0 {
+        write!(f, ".{:03}", millis % 1000)?;
+    }
+
+    match offset {
+        Some(0) => write!(f, "Z"),
+        Some(o) => {
+            let sign = if o < 0 { '-' } else { '+' };
+            write!(
+                f,
+                "{}{:02}:{:02}",
+                sign,
+                o.abs() / 3600,
+                o.abs() % 3600 / 60
+            )
+        }
+        None => Ok(()),
+    }
+}
+
+fn parse_ymd(s: &str) -> Option<(i64, i64, i64)> {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return None;
+    }
+
+    let y = parse_digits(parts[0])?;
+    let m = parse_digits(parts[1])?;
+    let d = parse_digits(parts[2])?;
+    Temporal::from_ymd(y, m, d).map(|_| (y, m, d))
+}
+
+/// "hh:mm(:ss(.fff))" followed by an optional "Z" or "+hh:mm" offset
+fn parse_hms(s: &str) -> Option<(i64, Option<i32>)> {
+    let (clock, offset) = if let Some(clock) = s.strip_suffix('Z') {
+        (clock, Some(0))
+    } else if let Some(i) = s.rfind(['+', '-']) {
+        let sign = if s[i..].starts_with('-') { -1 } else { 1 };
+        let (h, m) = s[i + 1..].split_once(':')?;
+        let (h, m) = (parse_digits(h)?, parse_digits(m)?);
+        if h > 14 || m > 59 {
+            return None;
+        }
+        (&s[..i], Some(sign * (h * 3600 + m * 60) as i32))
+    } else {
+        (s, None)
+    };
+
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 || parts[0].len() != 2 || parts[1].len() != 2 {
+        return None;
+    }
+
+    let h = parse_digits(parts[0])?;
+    let m = parse_digits(parts[1])?;
+    let (s, fraction) = match parts.get(2) {
+        Some(sec) => match sec.split_once('.') {
+            Some((whole, fraction)) => (parse_digits(whole)?, fraction),
+            None => (parse_digits(sec)?, ""),
+        },
+        None => (0, ""),
+    };
+    if h > 23 || m > 59 || s > 59 {
+        return None;
+    }
+
+    let mut millis = 0;
+    if !fraction.is_empty() {
+        // digits only, so the first 3 bytes are the first 3 characters
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let padded = format!("{:0<3}", fraction);
+        millis = parse_digits(&padded[..3])?;
+    }
+
+    Some(((h * 3600 + m * 60 + s) * MILLIS_PER_SECOND + millis, offset))
+}
+
+fn parse_digits(s: &str) -> Option<i64> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse::<i64>().ok()
+}
+
+/// "1Y2M" -> [(1, 'Y'), (2, 'M')]
+fn duration_parts(s: &str) -> Option<Vec<(f64, char)>> {
+    let mut parts = vec![];
+    let mut amount = String::new();
+
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            amount.push(c);
+        } else {
+            if amount.is_empty() {
+                return None;
+            }
+            parts.push((amount.parse::<f64>().ok()?, c));
+            amount.clear();
+        }
+    }
+
+    if !amount.is_empty() {
+        return None;
+    }
+
+    Some(parts)
+}
+
+fn add_months(days: i64, months: i64) -> Option<i64> {
+    if months == 0 {
+        return Some(days);
+    }
+
+    let (y, m, d) = civil_from_days(days);
+    let total = y.checked_mul(12)?.checked_add(m - 1)?.checked_add(months)?;
+    let (y, m) = (total.div_euclid(12), total.rem_euclid(12) + 1);
+
+    days_from_civil(y, m, d.min(days_in_month(y, m)))
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        4 | 6 | 9 | 11 => 30,
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Start of the day in milliseconds, None when it doesn't fit
+fn day_millis(days: i64) -> Option<i64> {
+    days.checked_mul(MILLIS_PER_DAY)
+}
+
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(y: i64, m: i64, d: i64) -> Option<i64> {
+    let y = if m <= 2 { y.checked_sub(1)? } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era.checked_mul(146_097)?.checked_add(doe - 719_468)
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_temporals() -> Result<(), String> {
+        let cases = vec![
+            (Temporal::parse_date("2024-02-29")?, "2024-02-29"),
+            (Temporal::parse_time("10:30:00")?, "10:30:00"),
+            (
+                Temporal::parse_time("10:30:00.5+02:00")?,
+                "10:30:00.500+02:00",
+            ),
+            (
+                Temporal::parse_date_time("2024-01-31T23:59:59Z")?,
+                "2024-01-31T23:59:59Z",
+            ),
+            (
+                Temporal::parse_date_time("1969-12-31")?,
+                "1969-12-31T00:00:00",
+            ),
+            (Temporal::parse_duration("P1Y14M")?, "P2Y2M"),
+            (Temporal::parse_duration("P3DT4H30M")?, "P3DT4H30M"),
+            (Temporal::parse_duration("-PT90S")?, "-PT1M30S"),
+            (Temporal::parse_duration("P0D")?, "PT0S"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(value.to_string(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fails_invalid_temporals() {
+        assert!(Temporal::parse_date("2023-02-29").is_err());
+        assert!(Temporal::parse_date("2023-1-01").is_err());
+        assert!(Temporal::parse_time("24:00:00").is_err());
+        assert!(Temporal::parse_date_time("2024-01-01T").is_err());
+        assert!(Temporal::parse_duration("P").is_err());
+        assert!(Temporal::parse_duration("P1.5D").is_err());
+        assert!(Temporal::parse_duration("3D").is_err());
+        assert!(Temporal::parse_time("10:00:00.éé").is_err());
+        assert!(Temporal::parse_time("10:00:00.1234é").is_err());
+        assert!(Temporal::parse_duration("P999999999999999999D").is_err());
+        assert!(Temporal::parse_duration("P99999999999999999999M").is_err());
+        assert!(Temporal::parse_duration("PT99999999999999999H").is_err());
+        assert_eq!(Temporal::from_ymd(999_999_999_999_999_999, 1, 1), None);
+        assert_eq!(Temporal::from_ymd(i64::MIN, 1, 1), None);
+    }
+
+    #[test]
+    fn fails_temporal_arithmetic_out_of_range() -> Result<(), String> {
+        let date = Temporal::parse_date("2024-01-31")?;
+        let long = Temporal::Duration {
+            months: i64::MAX,
+            millis: 0,
+        };
+        assert_eq!(date.add(&long), None);
+        assert_eq!(date.sub(&long), None);
+
+        let longest = Temporal::Duration {
+            months: 0,
+            millis: i64::MAX,
+        };
+        assert_eq!(longest.add(&longest), None);
+        assert_eq!(Temporal::parse_duration("-P1D")?.sub(&longest), None);
+        assert_eq!(
+            Temporal::Duration {
+                months: 0,
+                millis: i64::MIN
+            }
+            .to_string(),
+            "-P106751991167DT7H12M55.808S"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn succeeds_temporal_arithmetic() -> Result<(), String> {
+        let jan_31 = Temporal::parse_date("2024-01-31")?;
+        let month = Temporal::parse_duration("P1M")?;
+        assert_eq!(
+            jan_31.add(&month),
+            Some(Temporal::parse_date("2024-02-29")?)
+        );
+
+        let mar_1 = Temporal::parse_date("2024-03-01")?;
+        assert_eq!(mar_1.sub(&jan_31), Some(Temporal::parse_duration("P30D")?));
+
+        let late = Temporal::parse_time("23:00:00")?;
+        assert_eq!(
+            late.add(&Temporal::parse_duration("PT2H")?),
+            Some(Temporal::parse_time("01:00:00")?)
+        );
+
+        assert_eq!(
+            Temporal::parse_duration("PT1H")?.scale(1.5),
+            Some(Temporal::parse_duration("PT90M")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compares_temporals() -> Result<(), String> {
+        assert!(Temporal::parse_date("2024-01-31")? < Temporal::parse_date("2024-02-01")?);
+        assert_eq!(
+            Temporal::parse_date_time("2024-01-31T10:00:00+02:00")?,
+            Temporal::parse_date_time("2024-01-31T08:00:00Z")?
+        );
+        assert!(Temporal::parse_duration("P1D")? > Temporal::parse_duration("PT23H")?);
+        assert_eq!(
+            Temporal::parse_duration("P1M")?.partial_cmp(&Temporal::parse_duration("P30D")?),
+            None
+        );
+        assert_eq!(
+            Temporal::parse_date("2024-01-31")?.partial_cmp(&Temporal::parse_time("10:00:00")?),
+            None
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,23 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::bool_comparison,
+    clippy::get_first,
+    clippy::len_zero,
+    clippy::manual_range_contains,
+    clippy::needless_borrow,
+    clippy::needless_return,
+    clippy::nonminimal_bool,
+    clippy::op_ref,
+    clippy::partialeq_to_none,
+    clippy::while_let_loop
+)]
+
 use serde_json::{Number, Value};
-use std::iter::Enumerate;
 use std::iter::Peekable;
-use std::str::Chars;
+use std::str::CharIndices;
 
+use super::decimal::{Decimal, NumberMode};
+use super::functions::{is_function_name, is_function_name_prefix};
 use super::operand::{Operand, Operator};
 
 struct TokenRange {
@@ -36,17 +51,25 @@ type Program = Vec<Operand>;
 
 pub struct Tokenizer<'a> {
     expression: &'a str,
-    i: Peekable<Enumerate<Chars<'a>>>,
+    /// characters with their byte offset into `expression`
+    i: Peekable<CharIndices<'a>>,
     operands: Program,
+    number_mode: NumberMode,
 }
 
 impl<'a> Tokenizer<'a> {
     pub fn new(expression: &'a str) -> Self {
-        return Tokenizer {
+        Self::with_number_mode(expression, NumberMode::Float)
+    }
+
+    /// In `NumberMode::Decimal` number literals are read as exact decimals
+    pub fn with_number_mode(expression: &'a str, number_mode: NumberMode) -> Self {
+        Tokenizer {
             expression,
-            i: expression.chars().enumerate().peekable(),
+            i: expression.char_indices().peekable(),
             operands: Vec::with_capacity(expression.len()),
-        };
+            number_mode,
+        }
     }
 
     pub fn parse(&mut self) -> Result<(), String> {
@@ -75,39 +98,74 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn next_operand(&mut self) -> Result<Operand, String> {
-        while let Some(&(_index, c)) = self.i.peek() {
-            if c == '(' {
-                self.i.next();
-                return Ok(Operand::OpenParen);
-            } else if c == ')' {
-                self.i.next();
-                return Ok(Operand::CloseParen);
-            } else if c == '"' {
-                return self.consume_string();
-            } else if check_if_operand(&c) {
-                return self.consume_variable();
-            } else if check_if_operator(&c) {
-                return self.consume_operator();
-            } else if check_if_digit(&c) {
-                return self.consume_number();
-            } else {
-                return Err(format!("unknown symbol at index {}, {:?}", _index, c));
-            }
-        }
+        let Some(&(index, c)) = self.i.peek() else {
+            return Err("Reached end - unprocessed statements found".to_string());
+        };
 
-        Err("Reached end - unprocessed statements found".to_string())
+        if c == '(' {
+            self.i.next();
+            Ok(Operand::OpenParen)
+        } else if c == ')' {
+            self.i.next();
+            Ok(Operand::CloseParen)
+        } else if c == ',' {
+            self.i.next();
+            Ok(Operand::Comma)
+        } else if c == '?' {
+            // DMN input placeholder, replaced with the column input value by the caller
+            self.i.next();
+            Ok(Operand::Variable(INPUT_PLACEHOLDER.to_string()))
+        } else if c == '"' || c == '\'' {
+            self.consume_string(c)
+        } else if check_if_operand(&c) {
+            self.consume_variable()
+        } else if check_if_operator(&c) {
+            self.consume_operator()
+        } else if check_if_digit(&c) {
+            self.consume_number()
+        } else {
+            Err(format!("unknown symbol at index {}, {:?}", index, c))
+        }
     }
-    pub fn to_postfix(self) -> Result<Vec<Operand>, String> {
+
+    pub fn to_postfix(&self) -> Result<Vec<Operand>, String> {
         let mut stack: Vec<Operand> = Vec::with_capacity(50);
         let mut postfix: Vec<Operand> = Vec::with_capacity(self.operands.len());
+        // one entry per open paren - argument count if the paren belongs to a function call
+        let mut call_args: Vec<Option<usize>> = vec![];
+        let mut previous: Option<&Operand> = None;
 
-        for o in self.operands {
+        for o in self.operands.iter() {
             match o {
-                Operand::Primitive(_) | Operand::Variable(_) => {
-                    postfix.push(o);
+                Operand::Primitive(_)
+                | Operand::Temporal(_)
+                | Operand::Decimal(_)
+                | Operand::Variable(_) => {
+                    postfix.push(o.clone());
+                }
+                Operand::Function(_, _) => {
+                    stack.push(o.clone());
                 }
                 Operand::OpenParen => {
-                    stack.push(o);
+                    if let Some(Operand::Function(_, _)) = previous {
+                        call_args.push(Some(1));
+                    } else {
+                        call_args.push(None);
+                    }
+                    stack.push(o.clone());
+                }
+                Operand::Comma => {
+                    match call_args.last_mut() {
+                        Some(Some(count)) => *count += 1,
+                        _ => return Err("',' outside of function call".to_string()),
+                    }
+
+                    while let Some(s_item) = stack.last() {
+                        if *s_item == Operand::OpenParen {
+                            break;
+                        }
+                        postfix.push(stack.pop().unwrap());
+                    }
                 }
                 Operand::CloseParen => {
                     let mut found = false;
@@ -126,14 +184,25 @@ impl<'a> Tokenizer<'a> {
                     if found == false {
                         return Err("no matching opening paren".to_string());
                     }
+
+                    if let Some(Some(count)) = call_args.pop() {
+                        let arity = match previous {
+                            Some(Operand::OpenParen) => 0,
+                            _ => count,
+                        };
+
+                        if let Some(Operand::Function(name, _)) = stack.pop() {
+                            postfix.push(Operand::Function(name, arity));
+                        }
+                    }
                 }
-                Operand::OperatorToken(ref t) => {
+                Operand::OperatorToken(t) => {
                     if stack.len() == 0 {
-                        stack.push(o);
+                        stack.push(o.clone());
                     } else {
                         loop {
                             if let Some(Operand::OpenParen) = stack.last() {
-                                stack.push(o);
+                                stack.push(o.clone());
                                 break;
                             } else if let Some(Operand::OperatorToken(so)) = stack.last() {
                                 if precedence(so) >= precedence(&t) {
@@ -143,52 +212,83 @@ impl<'a> Tokenizer<'a> {
                                         return Err("stack underflow".to_string());
                                     }
                                 } else {
-                                    stack.push(o);
+                                    stack.push(o.clone());
                                     break;
                                 }
                             } else {
-                                stack.push(o);
+                                stack.push(o.clone());
                                 break;
                             }
                         }
                     }
                 }
             }
+
+            previous = Some(o);
         }
 
         while let Some(s_item) = stack.pop() {
-            if s_item == Operand::OpenParen {
-                return Err("no matching closing paren".to_string());
+            match s_item {
+                Operand::OpenParen => return Err("no matching closing paren".to_string()),
+                Operand::Function(name, _) => {
+                    return Err(format!("function \"{}\" is missing its arguments", name))
+                }
+                _ => postfix.push(s_item),
             }
-
-            postfix.push(s_item);
         }
 
         return Ok(postfix);
     }
 
-    fn consume_string(&mut self) -> Result<Operand, String> {
-        self.i.next();
-        let mut is_closed = false;
-        let mut range = TokenRange::new();
+    /// String literal in double or single quotes, with \\ escapes, e.g. "He said \"hi\""
+    fn consume_string(&mut self, quote: char) -> Result<Operand, String> {
+        let Some((started_at, _)) = self.i.next() else {
+            return Err("expected string literal".to_string());
+        };
+        let mut literal = String::new();
 
-        while let Some((_index, c)) = self.i.next() {
-            if c == '"' {
-                is_closed = true;
-                range.set_end(_index);
-                break;
-            } else {
-                range.set_start(_index);
+        while let Some((index, c)) = self.i.next() {
+            if c == quote {
+                return Ok(Operand::Primitive(Value::String(literal)));
+            }
+            if c != '\\' {
+                literal.push(c);
+                continue;
+            }
+
+            match self.i.next() {
+                Some((_, 'n')) => literal.push('\n'),
+                Some((_, 't')) => literal.push('\t'),
+                Some((_, 'r')) => literal.push('\r'),
+                Some((_, 'u')) => literal.push(self.consume_unicode_escape(index)?),
+                Some((_, c @ ('"' | '\'' | '\\'))) => literal.push(c),
+                Some((_, c)) => return Err(format!("unknown escape \\{} at {}", c, index)),
+                None => break,
             }
         }
 
-        if is_closed {
-            let str_literal = &self.expression[range.started_at..range.ended_at];
+        Err(format!("no closing {} at {}", quote, started_at))
+    }
 
-            return Ok(Operand::Primitive(Value::String(str_literal.to_string())));
-        } else {
-            return Err(format!("no closing \" at {}", range.started_at));
+    /// The `{1F600}` part of a `\u{1F600}` escape
+    fn consume_unicode_escape(&mut self, at: usize) -> Result<char, String> {
+        let invalid = || format!("invalid unicode escape at {}", at);
+
+        if !matches!(self.i.next(), Some((_, '{'))) {
+            return Err(invalid());
+        }
+        let mut hex = String::new();
+        for (_, c) in self.i.by_ref() {
+            if c == '}' {
+                return u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(invalid);
+            }
+            hex.push(c);
         }
+
+        Err(invalid())
     }
 
     fn consume_operator(&mut self) -> Result<Operand, String> {
@@ -226,35 +326,65 @@ impl<'a> Tokenizer<'a> {
             return Ok(Operand::OperatorToken(token));
         } else {
             return Err(format!(
-                "unsupported operator \"{:?}\" at {} ",
-                o, range.started_at
+                "unsupported operator \"{}\" at {} ",
+                operator, range.started_at
             ));
         }
     }
 
-    fn consume_variable(&mut self) -> Result<Operand, String> {
-        let mut range = TokenRange::new();
+    fn consume_word(i: &mut Peekable<CharIndices<'a>>) -> String {
+        let mut word = String::new();
 
-        while let Some(&(_index, c)) = self.i.peek() {
-            if check_if_operand(&c) {
-                range.set_start(_index);
-                self.i.next();
+        while let Some(&(_index, c)) = i.peek() {
+            if check_if_operand(&c) || (!word.is_empty() && check_if_identifier_part(&c)) {
+                word.push(c);
+                i.next();
             } else {
                 break;
             }
+        }
 
-            range.set_end(_index);
+        word
+    }
+
+    fn consume_variable(&mut self) -> Result<Operand, String> {
+        let mut variable = Self::consume_word(&mut self.i);
+
+        // function names may span several words, e.g. "string length"
+        while is_function_name_prefix(&variable) {
+            let mut lookahead = self.i.clone();
+            while let Some(&(_, ' ')) = lookahead.peek() {
+                lookahead.next();
+            }
+
+            let candidate = format!("{} {}", variable, Self::consume_word(&mut lookahead));
+            if is_function_name(&candidate) || is_function_name_prefix(&candidate) {
+                variable = candidate;
+                self.i = lookahead;
+            } else {
+                break;
+            }
         }
 
-        let variable = &self.expression[range.started_at..range.ended_at + 1];
+        let mut lookahead = self.i.clone();
+        while let Some(&(_, ' ')) | Some(&(_, '\t')) = lookahead.peek() {
+            lookahead.next();
+        }
+        if let Some(&(index, '(')) = lookahead.peek() {
+            if !is_function_name(&variable) {
+                return Err(format!("unknown function \"{}\" at {}", variable, index));
+            }
+            self.i = lookahead;
+            return Ok(Operand::Function(variable, 0));
+        }
 
         let reserved_bool_keywords = ["true", "false"];
 
-        if reserved_bool_keywords.contains(&variable) {
+        if reserved_bool_keywords.contains(&variable.as_str()) {
             return Ok(Operand::Primitive(Value::Bool(variable == "true")));
         }
 
-        return Ok(Operand::Variable(variable.to_string()));
+        return Ok(Operand::Variable(variable));
     }
 
     fn consume_number(&mut self) -> Result<Operand, String> {
@@ -289,15 +419,33 @@ impl<'a> Tokenizer<'a> {
             return Err(format!("empty number at {}", range.started_at));
         }
 
-        let res_number = number.parse::<f64>().unwrap();
-        let res_operand = Operand::Primitive(Value::Number(Number::from_f64(res_number).unwrap()));
-        return Ok(res_operand);
+        if self.number_mode == NumberMode::Decimal {
+            return Ok(Operand::Decimal(Decimal::parse(number)?));
+        }
+
+        // integer literals stay integers, unless they don't fit u64
+        if !has_dot {
+            if let Ok(integer) = number.parse::<u64>() {
+                return Ok(Operand::Primitive(Value::Number(Number::from(integer))));
+            }
+        }
+
+        number
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(|n| Operand::Primitive(Value::Number(n)))
+            .ok_or_else(|| format!("number out of range at {}", range.started_at))
     }
 
     pub fn insert_start(&mut self, o: Operand) {
         self.operands.insert(0, o);
     }
 
+    pub fn operands(&self) -> &[Operand] {
+        &self.operands
+    }
+
     pub fn starts_with_operand(&self) -> bool {
         if let Some(Operand::OperatorToken(_)) = self.operands.get(0) {
             return true;
@@ -305,10 +453,32 @@ impl<'a> Tokenizer<'a> {
 
         false
     }
+
+    /// Replaces every `?` in the expression with `o`, returns false if there was none
+    pub fn replace_placeholder(&mut self, o: &Operand) -> bool {
+        let mut replaced = false;
+
+        for operand in self.operands.iter_mut() {
+            if let Operand::Variable(name) = operand {
+                if name == INPUT_PLACEHOLDER {
+                    *operand = o.clone();
+                    replaced = true;
+                }
+            }
+        }
+
+        replaced
+    }
 }
 
+pub const INPUT_PLACEHOLDER: &str = "?";
+
 fn check_if_operand(c: &char) -> bool {
-    (c >= &'a' && c <= &'z') || (c >= &'A' && c <= &'Z')
+    c.is_ascii_alphabetic() || c == &'_'
+}
+
+fn check_if_identifier_part(c: &char) -> bool {
+    c.is_ascii_alphanumeric() || c == &'_' || c == &'.'
 }
 
 fn check_if_operator(c: &char) -> bool {
@@ -365,7 +535,7 @@ mod tests {
                 Operand::OperatorToken(Operator::Multiply),
                 Operand::OperatorToken(Operator::Plus),
                 Operand::Variable("d".to_string()),
-                Operand::Primitive(Value::Number(Number::from_f64(2.0).unwrap())),
+                Operand::Primitive(Value::Number(Number::from(2))),
                 Operand::OperatorToken(Operator::Multiply),
                 Operand::OperatorToken(Operator::Substract),
                 Operand::OperatorToken(Operator::LE),
@@ -373,6 +543,81 @@ mod tests {
         );
         Ok(())
     }
+    #[test]
+    fn parses_string_literals() -> Result<(), String> {
+        let cases = vec![
+            (r#""""#, ""),
+            (r#""Spaceribs""#, "Spaceribs"),
+            (r#""He said \"hi\"""#, "He said \"hi\""),
+            (r#""a | b \\ c\n""#, "a | b \\ c\n"),
+            (r#""\u{e9}t\u{E9}""#, "été"),
+            (r#"'It\'s "quoted"'"#, "It's \"quoted\""),
+            (r#""ünï""#, "ünï"),
+        ];
+
+        for (formula, expected) in cases {
+            let mut parser = Tokenizer::new(formula);
+            parser.parse()?;
+            assert_eq!(
+                parser.operands,
+                vec![Operand::Primitive(Value::String(expected.to_string()))],
+                "{}",
+                formula
+            );
+        }
+
+        assert!(Tokenizer::new(r#""open"#).parse().is_err());
+        assert!(Tokenizer::new(r#""\q""#).parse().is_err());
+        assert!(Tokenizer::new(r#""\u{zz}""#).parse().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parses_operators_and_numbers_after_non_ascii_strings() -> Result<(), String> {
+        let text = |t: &str| Operand::Primitive(Value::String(t.to_string()));
+        let cases = vec![
+            (
+                r#""été" = 10"#,
+                vec![
+                    text("été"),
+                    Operand::OperatorToken(Operator::E),
+                    Operand::Primitive(Value::Number(Number::from(10))),
+                ],
+            ),
+            (
+                r#""été" + "x""#,
+                vec![
+                    text("été"),
+                    Operand::OperatorToken(Operator::Plus),
+                    text("x"),
+                ],
+            ),
+            (
+                r#""ü" != 1.5"#,
+                vec![
+                    text("ü"),
+                    Operand::OperatorToken(Operator::NE),
+                    Operand::Primitive(Value::Number(Number::from_f64(1.5).unwrap())),
+                ],
+            ),
+            (
+                r#"'日本'<=2"#,
+                vec![
+                    text("日本"),
+                    Operand::OperatorToken(Operator::LE),
+                    Operand::Primitive(Value::Number(Number::from(2))),
+                ],
+            ),
+        ];
+
+        for (formula, expected) in cases {
+            let mut parser = Tokenizer::new(formula);
+            parser.parse()?;
+            assert_eq!(parser.operands, expected, "{}", formula);
+        }
+        Ok(())
+    }
+
     #[test]
     fn parses_no_paren_expression() -> Result<(), String> {
         let formula = "aA+b *c-d*2";
@@ -393,7 +638,7 @@ mod tests {
                 Operand::OperatorToken(Operator::Multiply),
                 Operand::OperatorToken(Operator::Plus),
                 Operand::Variable("d".to_string()),
-                Operand::Primitive(Value::Number(Number::from_f64(2.0).unwrap())),
+                Operand::Primitive(Value::Number(Number::from(2))),
                 Operand::OperatorToken(Operator::Multiply),
                 Operand::OperatorToken(Operator::Substract),
             ]
@@ -401,6 +646,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parses_function_calls() -> Result<(), String> {
+        let formula = "max(a, 2 * b) + string length(upper case(c.name))";
+
+        let mut parser = Tokenizer::new(&formula);
+        parser.parse()?;
+
+        let postfix = parser.to_postfix()?;
+        assert_eq!(
+            postfix,
+            vec![
+                Operand::Variable("a".to_string()),
+                Operand::Primitive(Value::Number(Number::from(2))),
+                Operand::Variable("b".to_string()),
+                Operand::OperatorToken(Operator::Multiply),
+                Operand::Function("max".to_string(), 2),
+                Operand::Variable("c.name".to_string()),
+                Operand::Function("upper case".to_string(), 1),
+                Operand::Function("string length".to_string(), 1),
+                Operand::OperatorToken(Operator::Plus),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fails_unknown_function_and_stray_comma() {
+        let mut parser = Tokenizer::new("shout(a)");
+        assert!(parser.parse().is_err());
+
+        let mut parser = Tokenizer::new("a, b");
+        assert!(parser.parse().is_ok());
+        assert!(parser.to_postfix().is_err());
+    }
+
     #[test]
     fn fails_expression_unknown_symbol() {
         let formula = "100.00<)^";
@@ -466,6 +746,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn fails_number_out_of_range() {
+        let formula = format!("{}.5", "9".repeat(400));
+        let mut parser = Tokenizer::new(&formula);
+        let err = parser.parse().unwrap_err();
+        assert!(err.contains("number out of range"), "{}", err);
+    }
+
     #[test]
     fn succeeds_single_number_element() -> Result<(), String> {
         let formula = "101.001";
@@ -533,7 +821,7 @@ mod tests {
             postfix?,
             vec![
                 Operand::Primitive(Value::Number(Number::from_f64(11.0).unwrap())),
-                Operand::Primitive(Value::Number(Number::from_f64(10.0).unwrap())),
+                Operand::Primitive(Value::Number(Number::from(10))),
                 Operand::OperatorToken(Operator::L)
             ]
         );
@@ -562,7 +850,7 @@ mod tests {
             postfix?,
             vec![
                 Operand::Primitive(Value::Number(Number::from_f64(11.0).unwrap())),
-                Operand::Primitive(Value::Number(Number::from_f64(10.0).unwrap())),
+                Operand::Primitive(Value::Number(Number::from(10))),
                 Operand::OperatorToken(Operator::E)
             ]
         );
@@ -618,27 +906,34 @@ mod tests {
         assert_eq!(is_postfix_valid(&postfix?), false);
         Ok(())
     }
-}
 
-fn is_postfix_valid(postfix: &Vec<Operand>) -> bool {
-    let mut stack: Vec<&Operand> = Vec::with_capacity(postfix.len());
-    let mut valid = false;
+    fn is_postfix_valid(postfix: &Vec<Operand>) -> bool {
+        let mut stack: Vec<&Operand> = Vec::with_capacity(postfix.len());
+        let mut valid = false;
 
-    for p in postfix {
-        valid = false;
+        for p in postfix {
+            valid = false;
 
-        if let Operand::OperatorToken(_o) = p {
-            let right = stack.pop().is_some();
-            let left = stack.pop().is_some();
+            if let Operand::OperatorToken(_o) = p {
+                let right = stack.pop().is_some();
+                let left = stack.pop().is_some();
 
-            if right == left {
+                if right == left {
+                    stack.push(&Operand::Primitive(Value::Null));
+                    valid = true;
+                }
+            } else if let Operand::Function(_name, arity) = p {
+                if stack.len() < *arity {
+                    return false;
+                }
+                stack.truncate(stack.len() - arity);
                 stack.push(&Operand::Primitive(Value::Null));
                 valid = true;
+            } else {
+                stack.push(p);
             }
-        } else {
-            stack.push(p);
         }
-    }
 
-    valid && stack.len() == 1
+        valid && stack.len() == 1
+    }
 }
@@ -0,0 +1,142 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+use super::trace::Trace;
+use super::{AllCombined, Environment};
+
+/*
+    Which branches of a rule a set of contexts exercised. Every operation
+    except `var` is a branch, keyed by its position in the rule ($ is the
+    rule itself, $.0.1 the second argument of its first argument). Branches
+    with a boolean result are covered once they were both true and false.
+*/
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct LogicCoverage {
+    pub runs: usize,
+    pub branches: Vec<BranchCoverage>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BranchCoverage {
+    pub path: String,
+    pub operation: String,
+    pub evaluated: usize,
+    #[serde(rename = "true")]
+    pub true_count: usize,
+    #[serde(rename = "false")]
+    pub false_count: usize,
+}
+
+impl BranchCoverage {
+    pub fn is_covered(&self) -> bool {
+        let boolean = self.true_count + self.false_count > 0;
+        self.evaluated > 0 && (!boolean || (self.true_count > 0 && self.false_count > 0))
+    }
+}
+
+impl LogicCoverage {
+    /// `execute_with` that counts the branches it went through
+    pub fn run(&mut self, rule: &AllCombined, context: &Value, env: &Environment) -> AllCombined {
+        let (result, trace) = rule.execute_traced(context, env);
+        self.record(&trace);
+        result
+    }
+
+    pub fn record(&mut self, trace: &Trace) {
+        self.runs += 1;
+        let mut index = 0;
+        self.record_branch(trace, "$".to_string(), &mut index);
+    }
+
+    /// Branches are kept in the order the trace is walked, the same for every run of a rule
+    fn record_branch(&mut self, trace: &Trace, path: String, index: &mut usize) {
+        if !["value", "list", "var"].contains(&trace.operation.as_str()) {
+            if self.branches.len() == *index {
+                self.branches.push(BranchCoverage {
+                    path: path.clone(),
+                    operation: trace.operation.clone(),
+                    evaluated: 0,
+                    true_count: 0,
+                    false_count: 0,
+                });
+            }
+
+            let branch = &mut self.branches[*index];
+            branch.evaluated += 1;
+            match trace.result {
+                AllCombined::Primitive(Value::Bool(true)) => branch.true_count += 1,
+                AllCombined::Primitive(Value::Bool(false)) => branch.false_count += 1,
+                _ => {}
+            }
+            *index += 1;
+        }
+
+        for (i, child) in trace.children.iter().enumerate() {
+            self.record_branch(child, format!("{}.{}", path, i), index);
+        }
+    }
+
+    pub fn uncovered(&self) -> impl Iterator<Item = &BranchCoverage> {
+        self.branches.iter().filter(|b| !b.is_covered())
+    }
+}
+
+impl fmt::Display for LogicCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let covered = self.branches.iter().filter(|b| b.is_covered()).count();
+        write!(
+            f,
+            "{} of {} branches covered in {} runs",
+            covered,
+            self.branches.len(),
+            self.runs
+        )?;
+
+        for branch in self.uncovered() {
+            let reason = if branch.evaluated == 0 {
+                "never evaluated"
+            } else if branch.true_count == 0 {
+                "never true"
+            } else {
+                "never false"
+            };
+            write!(f, "\n  {} ({}) {}", branch.path, branch.operation, reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_branches_never_true_or_false() -> serde_json::Result<()> {
+        let rule: AllCombined = serde_json::from_str(
+            r#"{ "and": [ { ">": [{ "var": "income" }, { "*": [{ "var": "loan" }, 0.3] }] }, { "=": [{ "var": "country" }, "NL"] } ] }"#,
+        )?;
+        let mut coverage = LogicCoverage::default();
+
+        for context in [
+            serde_json::json!({ "income": 1000, "loan": 3000, "country": "NL" }),
+            serde_json::json!({ "income": 100, "loan": 3000, "country": "NL" }),
+        ] {
+            let result = coverage.run(&rule, &context, &Environment::default());
+            assert_eq!(result, rule.execute(&context));
+        }
+
+        let paths: Vec<&str> = coverage.branches.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(paths, vec!["$", "$.0", "$.0.1", "$.1"]);
+        assert_eq!(
+            coverage.to_string(),
+            "3 of 4 branches covered in 2 runs\n  $.1 (=) never false"
+        );
+
+        let json = serde_json::to_value(&coverage)?;
+        assert_eq!(json["branches"][0]["true"], serde_json::json!(1));
+        assert_eq!(json["branches"][0]["false"], serde_json::json!(1));
+        Ok(())
+    }
+}
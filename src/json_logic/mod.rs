@@ -1,12 +1,24 @@
+#![allow(
+    clippy::len_zero,
+    clippy::match_like_matches_macro,
+    clippy::needless_return
+)]
+
+pub mod coverage;
+pub mod trace;
+
 use serde::{Deserialize, Serialize};
-use serde_json::{Number, Result, Value};
+use serde_json::{Number, Value};
 
 use crate::context::get_context_var;
+use crate::expression_parser::decimal::{Decimal, NumberMode};
+use crate::expression_parser::number;
+use crate::logic_table::graph::DecisionGraph;
 
 type OrderingOperation = Vec<AllCombined>;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
-enum Opss {
+pub enum Opss {
     #[serde(alias = ">")]
     More(OrderingOperation),
     #[serde(alias = "<")]
@@ -31,138 +43,134 @@ enum Opss {
     And(OrderingOperation),
     #[serde(alias = "var")]
     Var(String),
+    /// `{"decide": ["tableName", {"var": "order"}]}` - runs a decision, the context defaults to the current one
+    #[serde(alias = "decide")]
+    Decide(OrderingOperation),
+}
+
+/// What an evaluation can use besides its context
+#[derive(Clone, Copy, Default)]
+pub struct Environment<'a> {
+    pub number_mode: NumberMode,
+    /// decisions available to `decide`
+    pub decisions: Option<&'a DecisionGraph>,
 }
 
-fn binary_op_vars(l: &Vec<AllCombined>, context: &Value) -> Option<Vec<AllCombined>> {
+fn binary_op_vars(
+    l: &[AllCombined],
+    context: &Value,
+    env: &Environment,
+) -> Option<Vec<AllCombined>> {
     if l.len() < 2 {
         return None;
     }
-    Some(execute_combined_list(&l, context))
+    Some(execute_combined_list(l, context, env))
+}
+
+/// Arithmetic on the first two numbers of the list - integers and f64, or exact decimals in decimal mode
+fn number_op(
+    built_list: &[AllCombined],
+    env: &Environment,
+    float: fn(&Number, &Number) -> Option<Number>,
+    decimal: fn(&Decimal, &Decimal) -> Option<Decimal>,
+) -> AllCombined {
+    let res = match env.number_mode {
+        NumberMode::Float => match (&built_list[0], &built_list[1]) {
+            (
+                AllCombined::Primitive(Value::Number(n1)),
+                AllCombined::Primitive(Value::Number(n2)),
+            ) => float(n1, n2).map(|n| AllCombined::Primitive(Value::Number(n))),
+            _ => None,
+        },
+        // results stay decimals until they leave the evaluation, see `AllCombined::into_output`
+        NumberMode::Decimal => match (built_list[0].as_decimal(), built_list[1].as_decimal()) {
+            (Some(d1), Some(d2)) => decimal(&d1, &d2).map(AllCombined::Decimal),
+            _ => None,
+        },
+    };
+
+    res.unwrap_or(AllCombined::Primitive(Value::Null))
 }
 
 impl Opss {
-    fn execute(&self, context: &Value) -> AllCombined {
+    fn execute(&self, context: &Value, env: &Environment) -> AllCombined {
         match self {
             Opss::Less(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] < &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(built_list[0] < built_list[1]));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::More(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] > &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(built_list[0] > built_list[1]));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::Eq(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] == &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(
+                        built_list[0].equals(&built_list[1]),
+                    ));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::LessEq(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] <= &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(built_list[0] <= built_list[1]));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::MoreEq(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] >= &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(built_list[0] >= built_list[1]));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::Neq(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    return AllCombined::Primitive(Value::Bool(&built_list[0] != &built_list[1]));
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return AllCombined::Primitive(Value::Bool(
+                        !built_list[0].equals(&built_list[1]),
+                    ));
                 }
                 AllCombined::Primitive(Value::Bool(false))
             }
             Opss::Plus(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    match (&built_list[0], &built_list[1]) {
-                        (
-                            AllCombined::Primitive(Value::Number(n1)),
-                            AllCombined::Primitive(Value::Number(n2)),
-                        ) => {
-                            let res = n1.as_f64().unwrap_or(0.0) + n2.as_f64().unwrap_or(0.0);
-
-                            return AllCombined::Primitive(Value::Number(
-                                Number::from_f64(res).unwrap(),
-                            ));
-                        }
-                        (
-                            AllCombined::Primitive(Value::String(s1)),
-                            AllCombined::Primitive(Value::String(s2)),
-                        ) => return AllCombined::Primitive(Value::String(format!("{}{}", s1, s2))),
-                        (_, _1) => return AllCombined::Primitive(Value::Null),
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    if let (
+                        AllCombined::Primitive(Value::String(s1)),
+                        AllCombined::Primitive(Value::String(s2)),
+                    ) = (&built_list[0], &built_list[1])
+                    {
+                        return AllCombined::Primitive(Value::String(format!("{}{}", s1, s2)));
                     }
+
+                    return number_op(&built_list, env, number::add, |a, b| Some(a.add(b)));
                 }
                 AllCombined::Primitive(Value::Null)
             }
             Opss::Minus(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    match (&built_list[0], &built_list[1]) {
-                        (
-                            AllCombined::Primitive(Value::Number(n1)),
-                            AllCombined::Primitive(Value::Number(n2)),
-                        ) => {
-                            let res = n1.as_f64().unwrap_or(0.0) - n2.as_f64().unwrap_or(0.0);
-
-                            return AllCombined::Primitive(Value::Number(
-                                Number::from_f64(res).unwrap(),
-                            ));
-                        }
-                        (_, _1) => return AllCombined::Primitive(Value::Null),
-                    }
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return number_op(&built_list, env, number::sub, |a, b| Some(a.sub(b)));
                 }
                 AllCombined::Primitive(Value::Null)
             }
             Opss::Multiply(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    match (&built_list[0], &built_list[1]) {
-                        (
-                            AllCombined::Primitive(Value::Number(n1)),
-                            AllCombined::Primitive(Value::Number(n2)),
-                        ) => {
-                            let res = n1.as_f64().unwrap_or(0.0) * n2.as_f64().unwrap_or(0.0);
-
-                            return AllCombined::Primitive(Value::Number(
-                                Number::from_f64(res).unwrap(),
-                            ));
-                        }
-                        (_, _1) => return AllCombined::Primitive(Value::Null),
-                    }
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return number_op(&built_list, env, number::mul, |a, b| Some(a.mul(b)));
                 }
                 AllCombined::Primitive(Value::Null)
             }
             Opss::Division(l) => {
-                if let Some(built_list) = binary_op_vars(l, context) {
-                    match (&built_list[0], &built_list[1]) {
-                        (
-                            AllCombined::Primitive(Value::Number(n1)),
-                            AllCombined::Primitive(Value::Number(n2)),
-                        ) => {
-                            let second = n2.as_f64().unwrap_or(0.0);
-                            if second == 0.0 {
-                                return AllCombined::Primitive(Value::Null);
-                            }
-
-                            let res = n1.as_f64().unwrap_or(0.0) / second;
-
-                            return AllCombined::Primitive(Value::Number(
-                                Number::from_f64(res).unwrap(),
-                            ));
-                        }
-                        (_, _1) => return AllCombined::Primitive(Value::Null),
-                    }
+                if let Some(built_list) = binary_op_vars(l, context, env) {
+                    return number_op(&built_list, env, number::div, |a, b| a.div(b));
                 }
                 AllCombined::Primitive(Value::Null)
             }
-            Opss::Var(key) => AllCombined::Primitive(get_context_var(key, &context)),
+            Opss::Var(key) => AllCombined::Primitive(get_context_var(key, context)),
+            Opss::Decide(l) => AllCombined::Primitive(decide(l, context, env)),
             Opss::And(l) => {
-                let l_results = execute_combined_list(&l, context);
+                let l_results = execute_combined_list(l, context, env);
 
                 if l_results.len() == 0 {
                     return AllCombined::Primitive(Value::Bool(false));
@@ -179,8 +187,56 @@ impl Opss {
     }
 }
 
-fn execute_combined_list(l: &Vec<AllCombined>, context: &Value) -> Vec<AllCombined> {
-    l.iter().map(|l_item| l_item.execute(context)).collect()
+/*
+    Outputs of the matching rules as JSON, always an array of objects - empty
+    when nothing matches. A decision that is unknown or fails evaluates to
+    `{"error": "..."}` so the failure stays visible in the result.
+*/
+fn decide(l: &[AllCombined], context: &Value, env: &Environment) -> Value {
+    let Some(decisions) = env.decisions else {
+        return decide_error("no decisions available".to_string());
+    };
+    let args = execute_combined_list(l, context, env);
+
+    let name = match args.first() {
+        Some(AllCombined::Primitive(Value::String(name))) => name,
+        _ => return decide_error("decision name should be a string".to_string()),
+    };
+    let decision_context = match args.get(1) {
+        Some(AllCombined::Primitive(v)) => v,
+        _ => context,
+    };
+
+    match decisions.evaluate(name, decision_context) {
+        Ok(mut results) => Value::Array(
+            results
+                .iter_mut()
+                .map(|result| {
+                    Value::Object(
+                        result
+                            .drain()
+                            .map(|(key, operand)| (key, operand.to_value()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        ),
+        Err(e) => decide_error(e),
+    }
+}
+
+fn decide_error(message: String) -> Value {
+    serde_json::json!({ "error": message })
+}
+
+fn execute_combined_list(
+    l: &[AllCombined],
+    context: &Value,
+    env: &Environment,
+) -> Vec<AllCombined> {
+    l.iter()
+        .map(|l_item| l_item.evaluate(context, env))
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -189,42 +245,112 @@ pub enum AllCombined {
     Ops(Opss),
     OpList(Vec<AllCombined>),
     Primitive(Value),
+    /// exact intermediate result in decimal mode, written as a number
+    #[serde(skip_deserializing, serialize_with = "serialize_decimal")]
+    Decimal(Decimal),
+}
+
+fn serialize_decimal<S: serde::Serializer>(d: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    decimal_value(d).serialize(serializer)
+}
+
+fn decimal_value(d: &Decimal) -> Value {
+    match d.to_number() {
+        Some(n) => Value::Number(n),
+        None => Value::Null,
+    }
 }
 
 impl AllCombined {
+    /// Value equality, numbers compare by value - 2 equals 2.0
+    pub fn equals(&self, other: &AllCombined) -> bool {
+        if let Some((d1, d2)) = decimal_pair(self, other) {
+            return d1 == d2;
+        }
+
+        if let (
+            AllCombined::Primitive(Value::Number(n1)),
+            AllCombined::Primitive(Value::Number(n2)),
+        ) = (self, other)
+        {
+            return number::cmp(n1, n2) == std::cmp::Ordering::Equal;
+        }
+
+        self == other
+    }
+
     pub fn execute(&self, context: &Value) -> AllCombined {
+        self.execute_with(context, &Environment::default())
+    }
+
+    /// In `NumberMode::Decimal` arithmetic is exact, so 0.1 + 0.2 is 0.3
+    pub fn execute_with_mode(&self, context: &Value, number_mode: NumberMode) -> AllCombined {
+        let env = Environment {
+            number_mode,
+            ..Default::default()
+        };
+        self.execute_with(context, &env)
+    }
+
+    pub fn execute_with(&self, context: &Value, env: &Environment) -> AllCombined {
+        self.evaluate(context, env).into_output()
+    }
+
+    fn evaluate(&self, context: &Value, env: &Environment) -> AllCombined {
         match self {
             AllCombined::OpList(l) => {
-                let s: Vec<AllCombined> = execute_combined_list(&l, context);
+                let s: Vec<AllCombined> = execute_combined_list(l, context, env);
                 AllCombined::OpList(s)
             }
-            AllCombined::Ops(o) => o.execute(context),
+            AllCombined::Ops(o) => o.execute(context, env),
             AllCombined::Primitive(v) => AllCombined::Primitive(v.clone()),
+            AllCombined::Decimal(d) => AllCombined::Decimal(d.clone()),
+        }
+    }
+
+    /// The result as JSON values, decimals become numbers
+    fn into_output(self) -> AllCombined {
+        match self {
+            AllCombined::Decimal(d) => AllCombined::Primitive(decimal_value(&d)),
+            AllCombined::OpList(l) => {
+                AllCombined::OpList(l.into_iter().map(AllCombined::into_output).collect())
+            }
+            other => other,
+        }
+    }
+
+    fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            AllCombined::Decimal(d) => Some(d.clone()),
+            AllCombined::Primitive(Value::Number(n)) => Decimal::from_number(n),
+            _ => None,
+        }
+    }
+}
+
+/// Both sides as decimals when one of them already is one
+fn decimal_pair(a: &AllCombined, b: &AllCombined) -> Option<(Decimal, Decimal)> {
+    match (a, b) {
+        (AllCombined::Decimal(_), _) | (_, AllCombined::Decimal(_)) => {
+            Some((a.as_decimal()?, b.as_decimal()?))
         }
+        _ => None,
     }
 }
 
 // Ordering operations
 impl std::cmp::Ord for AllCombined {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if let Some((d1, d2)) = decimal_pair(self, other) {
+            return d1.cmp(&d2);
+        }
+
         match (self, other) {
             (
                 AllCombined::Primitive(Value::Number(n1)),
                 AllCombined::Primitive(Value::Number(n2)),
-            ) => {
-                // Todo, compare as i64 too
-                let n1_num = n1.as_f64().unwrap_or(0.0);
-                let n2_num = n2.as_f64().unwrap_or(0.0);
-                if n1_num > n2_num {
-                    return std::cmp::Ordering::Greater;
-                }
-                if n1_num < n2_num {
-                    return std::cmp::Ordering::Less;
-                }
-
-                return std::cmp::Ordering::Equal;
-            }
-            (_, _1) => std::cmp::Ordering::Equal,
+            ) => number::cmp(n1, n2),
+            (_, _) => std::cmp::Ordering::Equal,
         }
     }
 }
@@ -238,8 +364,11 @@ impl PartialOrd for AllCombined {
 impl Eq for AllCombined {}
 // end Ordering operations
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logic_table::document::parse_document;
+    use serde_json::Result;
 
     #[test]
     fn serializes_more_operator_enum_representation() -> Result<()> {
@@ -305,4 +434,150 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn keeps_integers() -> Result<()> {
+        let cases = vec![
+            (r#" {"+": [1, 1]} "#, "2"),
+            (r#" {"*": [1.5, 2]} "#, "3.0"),
+            (r#" {"/": [6, 3]} "#, "2"),
+            (r#" {"/": [1, 2]} "#, "0.5"),
+            (r#" {"+": [9007199254740993, 1]} "#, "9007199254740994"),
+            (r#" {"-": [{"var": "id"}, 1]} "#, "18446744073709551614"),
+        ];
+        let context: Value = serde_json::from_str(r#"{ "id": 18446744073709551615 }"#)?;
+
+        for (data, expected) in cases {
+            let p: AllCombined = serde_json::from_str(data)?;
+            let res = p.execute(&context);
+            assert_eq!(serde_json::to_string(&res)?, expected);
+        }
+
+        let p: AllCombined =
+            serde_json::from_str(r#" { ">" : [9007199254740993, 9007199254740992] }"#)?;
+        assert_eq!(
+            p.execute(&context),
+            AllCombined::Primitive(Value::Bool(true))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn executes_decision_tables() -> Result<()> {
+        let contents = std::fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+        let decisions = parse_document(&contents).and_then(DecisionGraph::new);
+        let decisions = decisions.expect("sample decisions should parse");
+        let env = Environment {
+            decisions: Some(&decisions),
+            ..Default::default()
+        };
+
+        let context: Value = serde_json::from_str(
+            r#"{ "order": { "season": "Fall", "guestCount": 4 }, "season": "Winter", "guestCount": 2 }"#,
+        )?;
+        let cases = vec![
+            (
+                r#"{"decide": ["Dish", {"var": "order"}]}"#,
+                r#"[{"desiredDish":"Spaceribs"}]"#,
+            ),
+            (r#"{"decide": ["Beverage"]}"#, r#"[{"beverage":"Wine"}]"#),
+            (
+                r#"{"=": [{"decide": ["Dish"]}, {"decide": ["Dish"]}]}"#,
+                "true",
+            ),
+            (r#"{"decide": ["Dish", {"season": "Summer"}]}"#, "[]"),
+            (
+                r#"{"decide": ["Dessert"]}"#,
+                r#"{"error":"unknown decision \"Dessert\""}"#,
+            ),
+            (
+                r#"{"decide": ["Dish", 5]}"#,
+                r#"{"error":"decision context has to be an object"}"#,
+            ),
+            (
+                r#"{"decide": [5]}"#,
+                r#"{"error":"decision name should be a string"}"#,
+            ),
+        ];
+
+        for (data, expected) in cases {
+            let p: AllCombined = serde_json::from_str(data)?;
+            let res = p.execute_with(&context, &env);
+            assert_eq!(serde_json::to_string(&res)?, expected, "{}", data);
+        }
+
+        let p: AllCombined = serde_json::from_str(r#"{"decide": ["Dish"]}"#)?;
+        assert_eq!(
+            serde_json::to_string(&p.execute(&context))?,
+            r#"{"error":"no decisions available"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn executes_decision_with_several_matches() -> Result<()> {
+        let contents = "## Sides\n| season | side |\n|---|--:|\n| string | string |\n| ## | ## |\n| \"Fall\" | \"Bread\" |\n| - | \"Salad\" |\n";
+        let decisions = parse_document(contents).and_then(DecisionGraph::new);
+        let decisions = decisions.expect("decisions should parse");
+        let env = Environment {
+            decisions: Some(&decisions),
+            ..Default::default()
+        };
+
+        let p: AllCombined = serde_json::from_str(r#"{"decide": ["Sides"]}"#)?;
+        let res = p.execute_with(&serde_json::json!({ "season": "Fall" }), &env);
+        assert_eq!(
+            serde_json::to_string(&res)?,
+            r#"[{"side":"Bread"},{"side":"Salad"}]"#
+        );
+        let res = p.execute_with(&serde_json::json!({ "season": "Spring" }), &env);
+        assert_eq!(serde_json::to_string(&res)?, r#"[{"side":"Salad"}]"#);
+        Ok(())
+    }
+
+    #[test]
+    fn executes_in_decimal_mode() -> Result<()> {
+        let cases = vec![
+            (r#" { "=" : [{"+": [0.1, 0.2]}, 0.3] }"#, true, false),
+            (r#" { "=" : [{"-": [0.3, 0.1]}, 0.2] }"#, true, false),
+            (r#" { "=" : [{"*": [1.1, 1.1]}, 1.21] }"#, true, false),
+            (r#" { "=" : [{"/": [0.3, 0.1]}, 3] }"#, true, false),
+            (r#" { "=" : [{"/": [6, 0]}, null] }"#, true, true),
+            (
+                r#" { "=" : [{"+": [{"+": [0.1, 0.2]}, 0.3]}, 0.6] }"#,
+                true,
+                false,
+            ),
+            (r#" { "<" : [{"*": [{"/": [1, 3]}, 3]}, 1] }"#, true, false),
+            (
+                r#" { "=" : [{"*": [{"+": [0.1, 0.2]}, 10]}, 3] }"#,
+                true,
+                false,
+            ),
+            (
+                r#" { "=" : [{"-": [{"+": [0.1, 100000000000000000]}, 100000000000000000]}, 0.1] }"#,
+                true,
+                false,
+            ),
+        ];
+
+        for (data, decimal_expected, float_expected) in cases {
+            let p: AllCombined = serde_json::from_str(data)?;
+            let res = p.execute_with_mode(&Value::Null, NumberMode::Decimal);
+            assert_eq!(res, AllCombined::Primitive(Value::Bool(decimal_expected)));
+
+            let env = Environment {
+                number_mode: NumberMode::Decimal,
+                ..Default::default()
+            };
+            let (traced, trace) = p.execute_traced(&Value::Null, &env);
+            assert_eq!(traced, res);
+            serde_json::to_string(&trace)?;
+
+            let res = p.execute(&Value::Null);
+            assert_eq!(res, AllCombined::Primitive(Value::Bool(float_expected)));
+        }
+        Ok(())
+    }
 }
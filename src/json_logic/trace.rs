@@ -0,0 +1,174 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{AllCombined, Environment, Opss};
+
+/*
+    Explained evaluation - every operation reports its result together with
+    the traces of its arguments. Arguments are evaluated once, the operation
+    then runs on their results.
+*/
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Trace {
+    /// operator, e.g. ">" or "var", "list" for lists and "value" for literals
+    pub operation: String,
+    pub result: AllCombined,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Trace>,
+}
+
+impl AllCombined {
+    pub fn execute_traced(&self, context: &Value, env: &Environment) -> (AllCombined, Trace) {
+        let trace = trace(self, context, env);
+        let result = clone_result(&trace.result).into_output();
+        (result, trace)
+    }
+}
+
+fn trace(expression: &AllCombined, context: &Value, env: &Environment) -> Trace {
+    match expression {
+        AllCombined::Primitive(_) | AllCombined::Decimal(_) => Trace {
+            operation: "value".to_string(),
+            result: clone_result(expression),
+            children: vec![],
+        },
+        AllCombined::OpList(l) => {
+            let children: Vec<Trace> = l.iter().map(|item| trace(item, context, env)).collect();
+            Trace {
+                operation: "list".to_string(),
+                result: AllCombined::OpList(
+                    children.iter().map(|c| clone_result(&c.result)).collect(),
+                ),
+                children,
+            }
+        }
+        AllCombined::Ops(Opss::Var(key)) => Trace {
+            operation: "var".to_string(),
+            result: Opss::Var(key.clone()).execute(context, env),
+            children: vec![],
+        },
+        AllCombined::Ops(op) => {
+            let children: Vec<Trace> = op
+                .args()
+                .iter()
+                .map(|arg| trace(arg, context, env))
+                .collect();
+            let evaluated =
+                op.with_args(children.iter().map(|c| clone_result(&c.result)).collect());
+            Trace {
+                operation: op.name().to_string(),
+                result: evaluated.execute(context, env),
+                children,
+            }
+        }
+    }
+}
+
+/// Results are always values or lists of values
+fn clone_result(result: &AllCombined) -> AllCombined {
+    match result {
+        AllCombined::Primitive(v) => AllCombined::Primitive(v.clone()),
+        AllCombined::Decimal(d) => AllCombined::Decimal(d.clone()),
+        AllCombined::OpList(l) => AllCombined::OpList(l.iter().map(clone_result).collect()),
+        AllCombined::Ops(_) => AllCombined::Primitive(Value::Null),
+    }
+}
+
+impl Opss {
+    fn name(&self) -> &'static str {
+        match self {
+            Opss::More(_) => ">",
+            Opss::Less(_) => "<",
+            Opss::Eq(_) => "=",
+            Opss::LessEq(_) => "<=",
+            Opss::MoreEq(_) => ">=",
+            Opss::Neq(_) => "!=",
+            Opss::Plus(_) => "+",
+            Opss::Minus(_) => "-",
+            Opss::Multiply(_) => "*",
+            Opss::Division(_) => "/",
+            Opss::And(_) => "and",
+            Opss::Var(_) => "var",
+            Opss::Decide(_) => "decide",
+        }
+    }
+
+    fn args(&self) -> &[AllCombined] {
+        match self {
+            Opss::More(l)
+            | Opss::Less(l)
+            | Opss::Eq(l)
+            | Opss::LessEq(l)
+            | Opss::MoreEq(l)
+            | Opss::Neq(l)
+            | Opss::Plus(l)
+            | Opss::Minus(l)
+            | Opss::Multiply(l)
+            | Opss::Division(l)
+            | Opss::And(l)
+            | Opss::Decide(l) => l,
+            Opss::Var(_) => &[],
+        }
+    }
+
+    /// The same operation on other arguments
+    fn with_args(&self, args: Vec<AllCombined>) -> Opss {
+        match self {
+            Opss::More(_) => Opss::More(args),
+            Opss::Less(_) => Opss::Less(args),
+            Opss::Eq(_) => Opss::Eq(args),
+            Opss::LessEq(_) => Opss::LessEq(args),
+            Opss::MoreEq(_) => Opss::MoreEq(args),
+            Opss::Neq(_) => Opss::Neq(args),
+            Opss::Plus(_) => Opss::Plus(args),
+            Opss::Minus(_) => Opss::Minus(args),
+            Opss::Multiply(_) => Opss::Multiply(args),
+            Opss::Division(_) => Opss::Division(args),
+            Opss::And(_) => Opss::And(args),
+            Opss::Var(key) => Opss::Var(key.clone()),
+            Opss::Decide(_) => Opss::Decide(args),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_intermediate_values() -> serde_json::Result<()> {
+        let p: AllCombined = serde_json::from_str(
+            r#"{ "and": [ { ">": [{ "var": "income" }, { "*": [{ "var": "loan" }, 0.3] }] }, { "=": [{ "var": "country" }, "NL"] } ] }"#,
+        )?;
+        let context = serde_json::json!({ "income": 1000, "loan": 3000, "country": "NL" });
+
+        let (result, trace) = p.execute_traced(&context, &Environment::default());
+        assert_eq!(result, AllCombined::Primitive(Value::Bool(true)));
+        assert_eq!(result, p.execute(&context));
+
+        assert_eq!(trace.operation, "and");
+        let more = &trace.children[0];
+        assert_eq!(more.operation, ">");
+        assert_eq!(
+            more.children[0].result,
+            AllCombined::Primitive(serde_json::json!(1000))
+        );
+        assert_eq!(more.children[1].operation, "*");
+        assert_eq!(
+            more.children[1].result,
+            AllCombined::Primitive(serde_json::json!(900.0))
+        );
+
+        let json = serde_json::to_value(&trace)?;
+        assert_eq!(
+            json["children"][1]["children"][0]["result"],
+            serde_json::json!("NL")
+        );
+        assert_eq!(
+            json["children"][1]["children"][1]["operation"],
+            serde_json::json!("value")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+extern crate serde_json;
+
+pub mod context;
+pub mod expression_parser;
+pub mod json_logic;
+pub mod logic_table;
+pub mod process;
+pub mod service;
@@ -0,0 +1,290 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::document::parse_document;
+use super::test_cases::parse_tests;
+use super::trace::{run_table_traced, CellOutcome, TableTrace};
+use super::Table;
+use crate::expression_parser::operand::Operand;
+
+/*
+    Which rules and cells a set of contexts exercised. Every run is traced
+    and counted, rules that were never hit are reported together with the
+    cells that never matched - the reason no context reached them. Runs that
+    fail are not counted, their errors are kept with the coverage instead.
+*/
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TableCoverage {
+    pub runs: usize,
+    pub rules: Vec<RuleCoverage>,
+    /// evaluations that failed, e.g. "line 5 failed: rules 1 and 2 both match, ..."
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RuleCoverage {
+    /// index into `Table::rows`
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub hits: usize,
+    pub cells: Vec<CellCoverage>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CellCoverage {
+    pub column: String,
+    pub expression: String,
+    pub matched: usize,
+    pub not_matched: usize,
+}
+
+/// Coverage of one decision of a document
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DecisionCoverage {
+    pub decision: String,
+    #[serde(flatten)]
+    pub coverage: TableCoverage,
+}
+
+impl TableCoverage {
+    pub fn new(table: &Table) -> Self {
+        let rules = table
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(row_index, row)| RuleCoverage {
+                row: row_index,
+                id: row.id.clone(),
+                hits: 0,
+                cells: table
+                    .defs
+                    .inputs
+                    .iter()
+                    .zip(row.cells.iter())
+                    .map(|((column, _), expression)| CellCoverage {
+                        column: column.clone(),
+                        expression: expression.clone(),
+                        matched: 0,
+                        not_matched: 0,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        TableCoverage {
+            runs: 0,
+            rules,
+            errors: vec![],
+        }
+    }
+
+    /// `run_table` that counts the rules and cells it went through
+    pub fn run(
+        &mut self,
+        table: &Table,
+        context: &Value,
+    ) -> Result<Vec<HashMap<String, Operand>>, String> {
+        let (outputs, trace) = run_table_traced(table, context)?;
+        self.record(&trace);
+        Ok(outputs)
+    }
+
+    pub fn record(&mut self, trace: &TableTrace) {
+        self.runs += 1;
+        for rule_trace in trace.rules.iter() {
+            let Some(rule) = self.rules.get_mut(rule_trace.row) else {
+                continue;
+            };
+            if rule_trace.matched {
+                rule.hits += 1;
+            }
+            for (cell, cell_trace) in rule.cells.iter_mut().zip(rule_trace.cells.iter()) {
+                match cell_trace.outcome {
+                    CellOutcome::Matched => cell.matched += 1,
+                    CellOutcome::NotMatched => cell.not_matched += 1,
+                    CellOutcome::Skipped => {}
+                }
+            }
+        }
+    }
+
+    /// Rules no run matched
+    pub fn uncovered(&self) -> impl Iterator<Item = &RuleCoverage> {
+        self.rules.iter().filter(|rule| rule.hits == 0)
+    }
+}
+
+impl fmt::Display for TableCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hit = self.rules.iter().filter(|rule| rule.hits > 0).count();
+        write!(
+            f,
+            "{} of {} rules hit in {} runs",
+            hit,
+            self.rules.len(),
+            self.runs
+        )?;
+
+        for error in self.errors.iter() {
+            write!(f, "\n  {}", error)?;
+        }
+        for rule in self.uncovered() {
+            write!(f, "\n  rule {}", rule.row + 1)?;
+            if let Some(id) = &rule.id {
+                write!(f, " ({})", id)?;
+            }
+            write!(f, " never hit")?;
+
+            for cell in rule.cells.iter().filter(|c| c.matched == 0) {
+                let reason = if cell.not_matched == 0 {
+                    "never tested"
+                } else {
+                    "never matched"
+                };
+                write!(f, "\n    {} {}: {}", cell.column, cell.expression, reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DecisionCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.decision, self.coverage)
+    }
+}
+
+/// Coverage of every decision by the test cases embedded in the document
+pub fn document_coverage(contents: &str) -> Result<Vec<DecisionCoverage>, String> {
+    let decisions = parse_document(contents)?;
+    let cases = parse_tests(contents)?;
+
+    let mut coverages: Vec<DecisionCoverage> = decisions
+        .iter()
+        .map(|d| DecisionCoverage {
+            decision: d.name.clone(),
+            coverage: TableCoverage::new(&d.table),
+        })
+        .collect();
+
+    for case in cases.iter() {
+        let found = decisions
+            .iter()
+            .zip(coverages.iter_mut())
+            .find(|(d, _)| d.name == case.decision);
+        if let Some((decision, coverage)) = found {
+            if let Err(e) = coverage.coverage.run(&decision.table, &case.context) {
+                coverage
+                    .coverage
+                    .errors
+                    .push(format!("{} failed: {}", case.name, e));
+            }
+        }
+    }
+
+    Ok(coverages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::parse;
+
+    #[test]
+    fn reports_rules_never_hit() -> Result<(), String> {
+        let contents = r#"
+        | rule | season   | guestCount | desiredDish |
+        |:-----|----------|------------|------------:|
+        | id   | string   | number     |      string |
+        | ##   | ##       | ##         |          ## |
+        | R-1  | "Fall"   | <= 8       | "Spaceribs" |
+        | R-2  | "Winter" | <= 8       | "Roastbeef" |
+        | R-3  | "Fall"   | > 8        | "Stew"      |
+        | R-4  | -        | > 100      | "Buffet"    |
+        "#;
+        let table = parse(contents)?;
+        let mut coverage = TableCoverage::new(&table);
+
+        for context in [
+            serde_json::json!({ "season": "Fall", "guestCount": 4 }),
+            serde_json::json!({ "season": "Fall", "guestCount": 10 }),
+        ] {
+            coverage.run(&table, &context)?;
+        }
+
+        assert_eq!(coverage.runs, 2);
+        let hits: Vec<usize> = coverage.rules.iter().map(|r| r.hits).collect();
+        assert_eq!(hits, vec![1, 0, 1, 0]);
+
+        assert_eq!(
+            coverage.to_string(),
+            "2 of 4 rules hit in 2 runs\n  rule 2 (R-2) never hit\n    season \"Winter\": never matched\n    guestCount <= 8: never tested\n  rule 4 (R-4) never hit\n    guestCount > 100: never matched"
+        );
+
+        let json = serde_json::to_value(&coverage).map_err(|e| e.to_string())?;
+        assert_eq!(json["rules"][1]["id"], serde_json::json!("R-2"));
+        assert_eq!(
+            json["rules"][1]["cells"][0]["notMatched"],
+            serde_json::json!(2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn covers_sample_document_with_its_tests() -> Result<(), String> {
+        let contents = std::fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+
+        let coverages = document_coverage(&contents)?;
+        let report: Vec<String> = coverages
+            .iter()
+            .map(|c| c.to_string().lines().next().unwrap_or_default().to_string())
+            .collect();
+        assert_eq!(
+            report,
+            vec![
+                "Dish: 2 of 3 rules hit in 3 runs",
+                "Beverage: 0 of 3 rules hit in 0 runs"
+            ]
+        );
+
+        let json = serde_json::to_value(&coverages[0]).map_err(|e| e.to_string())?;
+        assert_eq!(json["decision"], serde_json::json!("Dish"));
+        assert_eq!(json["runs"], serde_json::json!(3));
+        assert!(json.get("errors").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn reports_failing_runs() -> Result<(), String> {
+        let contents = std::fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file")
+            .replacen(
+                "| ##       | ##         |          ## |",
+                "| U        | ##         |          ## |",
+                1,
+            )
+            .replacen(
+                "| \"Fall\"   | > 8        |",
+                "| \"Fall\"   | >= 4       |",
+                1,
+            );
+
+        let coverages = document_coverage(&contents)?;
+        assert_eq!(coverages[0].coverage.runs, 2);
+        assert_eq!(
+            coverages[0].coverage.errors,
+            vec!["small fall failed: rules 1 and 3 both match, the hit policy is UNIQUE"]
+        );
+        assert!(coverages[0]
+            .to_string()
+            .starts_with("Dish: 1 of 3 rules hit in 2 runs\n  small fall failed: rules 1 and 3"));
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use super::{from_rows, Table};
+
+/*
+    Tables kept in spreadsheets. The first four rows are the header, the
+    direction (in, out, id, annotation or a markdown alignment like ---:),
+    the type and the hit policy row, then one rule per row - the same layout
+    as a markdown table. CSV follows RFC 4180 quoting, so a cell holding the
+    string literal "Fall" is exported as """Fall""". TSV has no quoting,
+    a cell is everything between two tabs.
+*/
+
+pub fn parse_csv(contents: &str) -> Result<Table, String> {
+    from_rows(directions(records(contents, ',', true)?)?)
+}
+
+pub fn parse_tsv(contents: &str) -> Result<Table, String> {
+    from_rows(directions(records(contents, '\t', false)?)?)
+}
+
+/// Direction words to the alignments `from_rows` expects
+fn directions(mut lines: Vec<(usize, Vec<String>)>) -> Result<Vec<(usize, Vec<String>)>, String> {
+    if lines.len() < 3 {
+        return Err("table definitions are not correct".to_string());
+    }
+
+    let line_number = lines[1].0;
+    for col_index in 0..lines[1].1.len() {
+        let direction = lines[1].1[col_index].to_lowercase();
+        let alignment = match direction.as_str() {
+            "in" | "input" => "---",
+            "out" | "output" => "---:",
+            "annotation" => ":---",
+            "id" => {
+                // the type row decides between rule id and annotation column
+                if let Some(var_type) = lines[2].1.get_mut(col_index) {
+                    *var_type = "id".to_string();
+                }
+                ":---"
+            }
+            _ if direction.contains('-') => continue,
+            _ => {
+                let header = lines[0].1.get(col_index).map(String::as_str);
+                return Err(format!(
+                    "line {}: column \"{}\" has an unknown direction \"{}\", use in, out, id or annotation",
+                    line_number,
+                    header.unwrap_or_default(),
+                    lines[1].1[col_index]
+                ));
+            }
+        };
+        lines[1].1[col_index] = alignment.to_string();
+    }
+
+    Ok(lines)
+}
+
+/// Trimmed cells of every non empty record with the line it starts on
+fn records(
+    contents: &str,
+    delimiter: char,
+    quoting: bool,
+) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut records: Vec<(usize, Vec<String>)> = vec![];
+    let mut record: Vec<String> = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if quoting && field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(field.trim().to_string());
+                field.clear();
+                finish_record(&mut record, &mut records, record_line);
+                line += 1;
+                record_line = line;
+            }
+            _ if c == delimiter => {
+                record.push(field.trim().to_string());
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted cell", record_line));
+    }
+    record.push(field.trim().to_string());
+    finish_record(&mut record, &mut records, record_line);
+
+    Ok(records)
+}
+
+/// Keeps the record unless every cell is empty, spreadsheets export blank rows as ",,,"
+fn finish_record(record: &mut Vec<String>, records: &mut Vec<(usize, Vec<String>)>, at: usize) {
+    let cells = std::mem::take(record);
+    if cells.iter().any(|c| !c.is_empty()) {
+        records.push((at, cells));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression_parser::operand::Operand;
+    use crate::logic_table::{parse, run_table};
+    use serde_json::Value;
+
+    #[test]
+    fn parses_csv_like_markdown() -> Result<(), String> {
+        let csv = "\u{feff}season,guestCount,desiredDish,notes\r\n\
+            in,in,out,annotation\r\n\
+            string,number,string,string\r\n\
+            U,##,##,##\r\n\
+            \"\"\"Fall\"\"\",<= 8,\"\"\"Spaceribs\"\"\",\"ribs, \"\"smoked\"\"\"\r\n\
+            \"\"\"Winter\"\"\",<= 8,\"\"\"Roastbeef\"\"\",\r\n\
+            ,,,\r\n";
+        let table = parse_csv(csv)?;
+
+        let markdown = r#"
+        | season   | guestCount | desiredDish | notes             |
+        |----------|------------|------------:|:------------------|
+        | string   | number     |      string | string            |
+        | U        | ##         |          ## | ##                |
+        | "Fall"   | <= 8       | "Spaceribs" | ribs, "smoked"    |
+        | "Winter" | <= 8       | "Roastbeef" |                   |
+        "#;
+        assert_eq!(table, parse(markdown)?);
+
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 4 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(
+            res[0].get("desiredDish"),
+            Some(&Operand::Primitive(Value::String("Roastbeef".to_owned())))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parses_tsv_with_ids() -> Result<(), String> {
+        let tsv = "rule\tseason\tdesiredDish\n\
+            id\tinput\toutput\n\
+            string\tstring\tstring\n\
+            ##\t##\t##\n\
+            R1\t\"Fall\"\t\"Stew\"\n";
+        let table = parse_tsv(tsv)?;
+
+        assert_eq!(table.defs.rule_id.as_deref(), Some("rule"));
+        assert_eq!(table.rows[0].id.as_deref(), Some("R1"));
+        assert_eq!(table.rows[0].cells, vec!["\"Fall\"", "\"Stew\""]);
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_bad_csv() {
+        let err = parse_csv("a,b\nin,sideways\nstring,string\n##,##\n1,2\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: column \"b\" has an unknown direction \"sideways\", use in, out, id or annotation"
+        );
+
+        let err = parse_csv("a,b\nin,out\nstring,string\n##,##\n1,\"2\n").unwrap_err();
+        assert_eq!(err, "line 5: unterminated quoted cell");
+
+        let err = parse_csv("a,b\n---,---:\nstring,string\n##,##\n1,2,3\n").unwrap_err();
+        assert_eq!(err, "line 5: expected 2 cells like the header row, found 3");
+    }
+}
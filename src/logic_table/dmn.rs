@@ -0,0 +1,680 @@
+use std::collections::HashSet;
+
+use super::document::Decision;
+use super::xml::{self, escape, escape_text, Element};
+use super::{hit_policy_name, is_wildcard, Definition, Row, Table, HIT_POLICIES};
+use crate::expression_parser::tokenizer::Tokenizer;
+
+/*
+    DMN 1.3 XML <decisionTable>s to tables and back. Inputs, outputs, rules,
+    the hit policy, rule ids and one annotation column are converted, entries
+    have to be FEEL the expression parser understands - disjunctions ("a","b")
+    and ranges ([1..10]) are rejected with the rule and column they are in.
+    Exported rule ids are kept in `label`, the XML ids are generated from
+    names and ids and made unique within the document.
+*/
+
+const DMN_NAMESPACE: &str = "https://www.omg.org/spec/DMN/20191111/MODEL/";
+
+/// header of the rule id column for imported rules with ids
+const RULE_ID_HEADER: &str = "rule";
+
+/// column type, DMN typeRef - other types are written as they are
+const TYPE_REFS: [(&str, &str); 2] = [
+    ("date and time", "dateTime"),
+    ("duration", "dayTimeDuration"),
+];
+
+/// Every decision table of a DMN document
+pub fn parse_dmn(contents: &str) -> Result<Vec<Decision>, String> {
+    let root = xml::parse(contents)?;
+    if root.name != "definitions" {
+        return Err(format!("expected <definitions>, found <{}>", root.name));
+    }
+
+    let mut decisions: Vec<Decision> = vec![];
+    for decision in root.children_named("decision") {
+        let name = decision
+            .attribute("name")
+            .or_else(|| decision.attribute("id"))
+            .ok_or_else(|| "decision without name or id".to_string())?
+            .to_string();
+        if decisions.iter().any(|d| d.name == name) {
+            return Err(format!("duplicate decision name \"{}\"", name));
+        }
+
+        let table = match decision.child("decisionTable") {
+            Some(table) => {
+                import_table(table).map_err(|e| format!("decision \"{}\": {}", name, e))?
+            }
+            None => {
+                return Err(format!(
+                    "decision \"{}\": only decision tables are supported",
+                    name
+                ))
+            }
+        };
+        decisions.push(Decision { name, table });
+    }
+
+    if decisions.is_empty() {
+        return Err("no decisions found".to_string());
+    }
+    Ok(decisions)
+}
+
+/// A DMN document with a decision table for every decision
+pub fn to_dmn(decisions: &[Decision]) -> Result<String, String> {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<definitions xmlns=\"{}\" id=\"definitions\" name=\"definitions\" namespace=\"https://github.com/md_logic\">\n",
+        DMN_NAMESPACE
+    ));
+
+    let mut ids = Ids::default();
+    for decision in decisions {
+        export_table(&mut out, decision, &mut ids)
+            .map_err(|e| format!("decision \"{}\": {}", decision.name, e))?;
+    }
+
+    out.push_str("</definitions>\n");
+    Ok(out)
+}
+
+fn import_table(element: &Element) -> Result<Table, String> {
+    let hit_policy = match element.attribute("hitPolicy") {
+        None => None,
+        Some(policy) => {
+            // evaluation has no aggregations
+            if let Some(aggregation) = element.attribute("aggregation") {
+                return Err(format!(
+                    "unsupported hit policy \"{}\" with aggregation \"{}\"",
+                    policy, aggregation
+                ));
+            }
+            let (abbreviation, _) = HIT_POLICIES
+                .iter()
+                .find(|(_, dmn)| *dmn == policy)
+                .ok_or_else(|| format!("unsupported hit policy \"{}\"", policy))?;
+            Some(abbreviation.to_string())
+        }
+    };
+
+    let mut inputs: Vec<(String, String)> = vec![];
+    for input in element.children_named("input") {
+        let header = input
+            .child("inputExpression")
+            .and_then(|e| e.child("text"))
+            .map(|t| t.text.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| "input without an input expression".to_string())?;
+        let type_ref = input
+            .child("inputExpression")
+            .and_then(|e| e.attribute("typeRef"));
+        inputs.push((header, import_type(type_ref)));
+    }
+
+    let mut outputs: Vec<(String, String)> = vec![];
+    for output in element.children_named("output") {
+        let header = output
+            .attribute("name")
+            .or_else(|| output.attribute("label"))
+            .ok_or_else(|| "output without a name".to_string())?;
+        outputs.push((header.to_string(), import_type(output.attribute("typeRef"))));
+    }
+
+    if inputs.is_empty() || outputs.is_empty() {
+        return Err("a table needs at least 1 input and 1 output".to_string());
+    }
+
+    let annotations: Vec<&Element> = element.children_named("annotation").collect();
+    if annotations.len() > 1 {
+        return Err("only one annotation column is supported".to_string());
+    }
+    let rules: Vec<&Element> = element.children_named("rule").collect();
+    if rules.is_empty() {
+        return Err("table has no rules".to_string());
+    }
+
+    let mut rows: Vec<Row> = vec![];
+    for (rule_index, rule) in rules.iter().enumerate() {
+        // exported rules keep the rule id as it was written in `label`
+        let id = rule.attribute("label").or_else(|| rule.attribute("id"));
+        let rule_name = id
+            .map(|id| format!("rule \"{}\"", id))
+            .unwrap_or_else(|| format!("rule {}", rule_index + 1));
+
+        let input_entries: Vec<&Element> = rule.children_named("inputEntry").collect();
+        let output_entries: Vec<&Element> = rule.children_named("outputEntry").collect();
+        if input_entries.len() != inputs.len() || output_entries.len() != outputs.len() {
+            return Err(format!(
+                "{}: expected {} input and {} output entries",
+                rule_name,
+                inputs.len(),
+                outputs.len()
+            ));
+        }
+
+        let mut cells: Vec<String> = vec![];
+        for (entry, (header, _)) in input_entries.iter().zip(inputs.iter()) {
+            let cell = import_input_entry(entry_text(entry))
+                .map_err(|e| format!("{}, input \"{}\": {}", rule_name, header, e))?;
+            cells.push(cell);
+        }
+        for (entry, (header, _)) in output_entries.iter().zip(outputs.iter()) {
+            let cell = import_output_entry(entry_text(entry))
+                .map_err(|e| format!("{}, output \"{}\": {}", rule_name, header, e))?;
+            cells.push(cell);
+        }
+
+        // Camunda keeps annotations in <description>
+        let annotation = rule
+            .child("annotationEntry")
+            .map(entry_text)
+            .or_else(|| rule.child("description").map(|d| d.text.trim()))
+            .filter(|a| !a.is_empty())
+            .map(|a| a.replace('\n', " "));
+
+        rows.push(Row {
+            cells,
+            id: id.map(str::to_string),
+            annotation,
+        });
+    }
+
+    let rule_id = rows
+        .iter()
+        .any(|row| row.id.is_some())
+        .then(|| RULE_ID_HEADER.to_string());
+    let annotation = match annotations.first() {
+        Some(a) => Some(a.attribute("name").unwrap_or("annotation").to_string()),
+        None => rows
+            .iter()
+            .any(|row| row.annotation.is_some())
+            .then(|| "annotation".to_string()),
+    };
+
+    let defs = Definition {
+        inputs,
+        outputs,
+        rule_id,
+        annotation,
+        hit_policy,
+    };
+    defs.check_result_headers()?;
+    Ok(Table { rows, defs })
+}
+
+fn entry_text(entry: &Element) -> &str {
+    entry.child("text").map(|t| t.text.trim()).unwrap_or("")
+}
+
+fn import_type(type_ref: Option<&str>) -> String {
+    match type_ref {
+        None => "any".to_string(),
+        Some("yearMonthDuration") => "duration".to_string(),
+        Some(type_ref) => TYPE_REFS
+            .iter()
+            .find(|(_, dmn)| *dmn == type_ref)
+            .map(|(column, _)| column.to_string())
+            .unwrap_or_else(|| type_ref.to_string()),
+    }
+}
+
+fn export_type(column_type: &str) -> &str {
+    TYPE_REFS
+        .iter()
+        .find(|(column, _)| *column == column_type)
+        .map(|(_, dmn)| *dmn)
+        .unwrap_or(column_type)
+}
+
+/// FEEL unary test to an input cell, `not("a")` becomes `!= "a"`
+fn import_input_entry(text: &str) -> Result<String, String> {
+    if is_wildcard(text) {
+        return Ok("-".to_string());
+    }
+    check_feel(text)?;
+
+    let cell = match text.strip_prefix("not(").and_then(|t| t.strip_suffix(')')) {
+        Some(negated) => {
+            // the tests inside not(...) are held to the same rules
+            check_feel(negated.trim()).map_err(|e| format!("in \"{}\": {}", text, e))?;
+            format!("!= {}", negated.trim())
+        }
+        None => text.to_string(),
+    };
+    parses(&cell)?;
+    Ok(cell)
+}
+
+fn import_output_entry(text: &str) -> Result<String, String> {
+    if text.is_empty() {
+        return Err("empty output entries are not supported".to_string());
+    }
+    check_feel(text)?;
+    parses(text)?;
+    Ok(text.to_string())
+}
+
+/// Rejects the FEEL the expression parser would misread
+fn check_feel(text: &str) -> Result<(), String> {
+    let unquoted = without_strings(text);
+    if unquoted.contains("..") {
+        return Err(format!(
+            "unsupported FEEL \"{}\": ranges are not supported, use comparisons",
+            text
+        ));
+    }
+
+    let mut depth = 0;
+    for c in unquoted.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                return Err(format!(
+                    "unsupported FEEL \"{}\": lists of tests are not supported, split the rule",
+                    text
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    if unquoted.contains(" or ") || unquoted.contains(" and ") {
+        return Err(format!(
+            "unsupported FEEL \"{}\": and/or are not supported, split the rule",
+            text
+        ));
+    }
+    Ok(())
+}
+
+fn parses(cell: &str) -> Result<(), String> {
+    let mut parser = Tokenizer::new(cell);
+    parser
+        .parse()
+        .and_then(|_| parser.to_postfix())
+        .map(|_| ())
+        .map_err(|e| format!("unsupported FEEL \"{}\": {}", cell, e.trim()))
+}
+
+/// The text with the contents of string literals removed
+fn without_strings(text: &str) -> String {
+    let mut unquoted = String::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => {
+                quote = None;
+                unquoted.push(c);
+            }
+            Some(_) => {}
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                unquoted.push(c);
+            }
+        }
+    }
+    unquoted
+}
+
+/// An input cell to a FEEL unary test, `!= "a"` becomes `not("a")`
+fn export_input_entry(cell: &str) -> String {
+    let cell = cell.trim();
+    if is_wildcard(cell) {
+        return "-".to_string();
+    }
+    if let Some(negated) = cell.strip_prefix("!=") {
+        return format!("not({})", negated.trim());
+    }
+    match cell.strip_prefix('=') {
+        Some(value) if !value.starts_with('=') => value.trim().to_string(),
+        _ => cell.to_string(),
+    }
+}
+
+fn export_table(out: &mut String, decision: &Decision, ids: &mut Ids) -> Result<(), String> {
+    let table = &decision.table;
+    let id = ids.unique(&decision.name);
+
+    out.push_str(&format!(
+        "  <decision id=\"{}\" name=\"{}\">\n",
+        id,
+        escape(&decision.name)
+    ));
+
+    let mut hit_policy = String::new();
+    if let Some(policy) = &table.defs.hit_policy {
+        let dmn = hit_policy_name(policy)
+            .ok_or_else(|| format!("hit policy \"{}\" has no DMN equivalent", policy))?;
+        hit_policy = format!(" hitPolicy=\"{}\"", dmn);
+    }
+    out.push_str(&format!(
+        "    <decisionTable id=\"{}\"{}>\n",
+        ids.unique(&format!("{}_table", id)),
+        hit_policy
+    ));
+
+    for (index, (header, column_type)) in table.defs.inputs.iter().enumerate() {
+        out.push_str(&format!(
+            "      <input id=\"{input_id}\" label=\"{label}\">\n        <inputExpression id=\"{expression_id}\" typeRef=\"{type_ref}\">\n          <text>{text}</text>\n        </inputExpression>\n      </input>\n",
+            input_id = ids.unique(&format!("{}_input_{}", id, index + 1)),
+            expression_id = ids.unique(&format!("{}_input_{}_expression", id, index + 1)),
+            label = escape(header),
+            text = escape_text(header),
+            type_ref = escape(export_type(column_type)),
+        ));
+    }
+    for (index, (header, column_type)) in table.defs.outputs.iter().enumerate() {
+        out.push_str(&format!(
+            "      <output id=\"{}\" name=\"{}\" typeRef=\"{}\" />\n",
+            ids.unique(&format!("{}_output_{}", id, index + 1)),
+            escape(header),
+            escape(export_type(column_type))
+        ));
+    }
+    if let Some(annotation) = &table.defs.annotation {
+        out.push_str(&format!(
+            "      <annotation name=\"{}\" />\n",
+            escape(annotation)
+        ));
+    }
+
+    let input_count = table.defs.inputs.len();
+    for row in table.rows.iter() {
+        // ids are optional, rules without one come back without a rule id column
+        match row.id.as_deref().filter(|id| !id.is_empty()) {
+            Some(rule_id) => out.push_str(&format!(
+                "      <rule id=\"{}\" label=\"{}\">\n",
+                ids.unique(rule_id),
+                escape(rule_id)
+            )),
+            None => out.push_str("      <rule>\n"),
+        }
+
+        for (col_index, cell) in row.cells.iter().enumerate() {
+            let (element, text) = if col_index < input_count {
+                ("inputEntry", export_input_entry(cell))
+            } else {
+                ("outputEntry", cell.trim().to_string())
+            };
+            out.push_str(&format!(
+                "        <{element}>\n          <text>{}</text>\n        </{element}>\n",
+                escape_text(&text),
+                element = element
+            ));
+        }
+        if table.defs.annotation.is_some() {
+            out.push_str(&format!(
+                "        <annotationEntry>\n          <text>{}</text>\n        </annotationEntry>\n",
+                escape_text(row.annotation.as_deref().unwrap_or(""))
+            ));
+        }
+
+        out.push_str("      </rule>\n");
+    }
+
+    out.push_str("    </decisionTable>\n  </decision>\n");
+    Ok(())
+}
+
+/// XML ids handed out in a document, each one only once
+#[derive(Default)]
+struct Ids(HashSet<String>);
+
+impl Ids {
+    /// `name` as an XML id, with a `_2`, `_3`... suffix when it is taken
+    fn unique(&mut self, name: &str) -> String {
+        let base = to_id(name);
+        let mut id = base.clone();
+        let mut n = 2;
+        while !self.0.insert(id.clone()) {
+            id = format!("{}_{}", base, n);
+            n += 1;
+        }
+        id
+    }
+}
+
+/// Rule ids and decision names as XML ids - letters, digits, `_`, `-` and `.`, not starting with a digit
+fn to_id(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || ['_', '-', '.'].contains(&c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression_parser::operand::Operand;
+    use crate::logic_table::document::parse_document;
+    use crate::logic_table::run_table;
+    use serde_json::Value;
+
+    const CAMUNDA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<definitions xmlns="https://www.omg.org/spec/DMN/20191111/MODEL/" id="dish" name="Dish" namespace="http://camunda.org/schema/1.0/dmn">
+  <decision id="Decision_dish" name="Dish">
+    <decisionTable id="DecisionTable_1" hitPolicy="FIRST">
+      <input id="Input_1" label="Season">
+        <inputExpression id="InputExpression_1" typeRef="string">
+          <text>season</text>
+        </inputExpression>
+      </input>
+      <input id="Input_2" label="Guests">
+        <inputExpression id="InputExpression_2" typeRef="integer">
+          <text>guestCount</text>
+        </inputExpression>
+      </input>
+      <output id="Output_1" label="Dish" name="desiredDish" typeRef="string" />
+      <rule id="DecisionRule_1">
+        <description>small &amp; cosy</description>
+        <inputEntry id="UnaryTests_1"><text>"Fall"</text></inputEntry>
+        <inputEntry id="UnaryTests_2"><text>&lt;= 8</text></inputEntry>
+        <outputEntry id="LiteralExpression_1"><text>"Spaceribs"</text></outputEntry>
+      </rule>
+      <rule id="DecisionRule_2">
+        <inputEntry id="UnaryTests_3"><text>not("Fall")</text></inputEntry>
+        <inputEntry id="UnaryTests_4"><text></text></inputEntry>
+        <outputEntry id="LiteralExpression_2"><text>"Stew"</text></outputEntry>
+      </rule>
+    </decisionTable>
+  </decision>
+</definitions>"#;
+
+    #[test]
+    fn imports_camunda_dmn() -> Result<(), String> {
+        let decisions = parse_dmn(CAMUNDA)?;
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].name, "Dish");
+
+        let table = &decisions[0].table;
+        assert_eq!(table.defs.hit_policy.as_deref(), Some("F"));
+        assert_eq!(
+            table.defs.inputs,
+            vec![
+                ("season".to_string(), "string".to_string()),
+                ("guestCount".to_string(), "integer".to_string())
+            ]
+        );
+        assert_eq!(table.defs.rule_id.as_deref(), Some("rule"));
+        assert_eq!(table.defs.annotation.as_deref(), Some("annotation"));
+        assert_eq!(table.rows[0].annotation.as_deref(), Some("small & cosy"));
+        assert_eq!(table.rows[1].cells, vec!["!= \"Fall\"", "-", "\"Stew\""]);
+
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 4 });
+        let res = run_table(table, &context)?;
+        assert_eq!(
+            res[0].get("desiredDish"),
+            Some(&Operand::Primitive(Value::String("Stew".to_owned())))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_markdown_decisions() -> Result<(), String> {
+        let contents = std::fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+        let decisions = parse_document(&contents)?;
+
+        let dmn = to_dmn(&decisions)?;
+        assert!(dmn.contains("<text>&lt;= 8</text>"), "{}", dmn);
+
+        let imported = parse_dmn(&dmn)?;
+        assert_eq!(imported.len(), decisions.len());
+        for (imported, original) in imported.iter().zip(decisions.iter()) {
+            assert_eq!(imported.name, original.name);
+            assert_eq!(imported.table, original.table);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn exports_ids_annotations_and_negations() -> Result<(), String> {
+        let contents = r#"
+        | rule | season   | dish   | notes  |
+        |:-----|----------|-------:|:-------|
+        | id   | string   | string | string |
+        | C    | ##       |     ## | ##     |
+        | R 1  | != "Fall" | "Stew" | a < b  |
+        "#;
+        let table = crate::logic_table::parse(contents)?;
+        let decisions = vec![Decision {
+            name: "1st dish".to_string(),
+            table,
+        }];
+
+        let dmn = to_dmn(&decisions)?;
+        assert!(dmn.contains("<decision id=\"_1st_dish\" name=\"1st dish\">"));
+        assert!(dmn.contains("hitPolicy=\"COLLECT\""));
+        assert!(dmn.contains("<rule id=\"R_1\" label=\"R 1\">"));
+        assert!(dmn.contains("<text>not(\"Fall\")</text>"));
+        assert!(dmn.contains("<annotation name=\"notes\" />"));
+        assert!(dmn.contains("<text>a &lt; b</text>"));
+
+        let imported = parse_dmn(&dmn)?;
+        assert_eq!(imported[0].table.rows[0].id.as_deref(), Some("R 1"));
+        assert_eq!(imported[0].table.rows[0].cells[0], "!= \"Fall\"");
+        assert_eq!(
+            imported[0].table.rows[0].annotation.as_deref(),
+            Some("a < b")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn exports_unique_ids() -> Result<(), String> {
+        let contents = r#"
+        | rule | season   | dish   |
+        |:-----|----------|-------:|
+        | id   | string   | string |
+        | U    | ##       |     ## |
+        | R 1  | "Fall"   | "Stew" |
+        | R_1  | "Winter" | "Beef" |
+        "#;
+        let decisions: Vec<Decision> = ["Dish size", "Dish_size"]
+            .iter()
+            .map(|name| {
+                Ok(Decision {
+                    name: name.to_string(),
+                    table: crate::logic_table::parse(contents)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let dmn = to_dmn(&decisions)?;
+        let ids: Vec<&str> = dmn
+            .split(" id=\"")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect();
+        let unique: HashSet<&str> = ids.iter().copied().collect();
+        assert_eq!(unique.len(), ids.len(), "{}", dmn);
+        assert!(dmn.contains("<decision id=\"Dish_size_2\" name=\"Dish_size\">"));
+        assert!(dmn.contains("<rule id=\"R_1_2\" label=\"R_1\">"));
+
+        let imported = parse_dmn(&dmn)?;
+        for (imported, original) in imported.iter().zip(decisions.iter()) {
+            assert_eq!(imported.name, original.name);
+            assert_eq!(imported.table, original.table);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_unsupported_feel() {
+        let dmn = CAMUNDA.replace("&lt;= 8", "[1..8]");
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert_eq!(
+            err,
+            "decision \"Dish\": rule \"DecisionRule_1\", input \"guestCount\": unsupported FEEL \"[1..8]\": ranges are not supported, use comparisons"
+        );
+
+        let dmn = CAMUNDA.replace("not(\"Fall\")", "\"Winter\",\"Spring\"");
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(
+            err.contains("rule \"DecisionRule_2\", input \"season\""),
+            "{}",
+            err
+        );
+        assert!(err.contains("lists of tests are not supported"), "{}", err);
+
+        let dmn = CAMUNDA.replace("not(\"Fall\")", "not(\"Fall\",\"Winter\")");
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(
+            err.contains("rule \"DecisionRule_2\", input \"season\""),
+            "{}",
+            err
+        );
+        assert!(err.contains("lists of tests are not supported"), "{}", err);
+
+        let dmn = CAMUNDA.replace(
+            "hitPolicy=\"FIRST\"",
+            "hitPolicy=\"COLLECT\" aggregation=\"AVG\"",
+        );
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(err.contains("unsupported hit policy \"COLLECT\" with aggregation \"AVG\""));
+
+        // evaluation has no aggregations or priorities
+        let dmn = CAMUNDA.replace(
+            "hitPolicy=\"FIRST\"",
+            "hitPolicy=\"COLLECT\" aggregation=\"SUM\"",
+        );
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(err.contains("unsupported hit policy \"COLLECT\" with aggregation \"SUM\""));
+        let dmn = CAMUNDA.replace("hitPolicy=\"FIRST\"", "hitPolicy=\"PRIORITY\"");
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(
+            err.contains("unsupported hit policy \"PRIORITY\""),
+            "{}",
+            err
+        );
+
+        let dmn = CAMUNDA.replace("name=\"desiredDish\"", "name=\"rule\"");
+        let err = parse_dmn(&dmn).unwrap_err();
+        assert!(
+            err.contains("rule id column \"rule\" has the name of an output"),
+            "{}",
+            err
+        );
+    }
+}
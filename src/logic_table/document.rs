@@ -0,0 +1,279 @@
+use super::writer::to_markdown;
+use super::{parse, split_row, Table};
+
+/*
+    Decision tables embedded in an ordinary markdown document.
+    A table starts at a row followed by a delimiter row (|---|--:|) and ends
+    at the first blank or non table line. Its name is the nearest heading above,
+    tables inside fenced code blocks are skipped. Only tables with a hit policy
+    row (`##`) under the type row are decisions, any other table is prose.
+*/
+
+#[derive(Debug)]
+pub struct Decision {
+    pub name: String,
+    pub table: Table,
+}
+
+/// Lines `start..end` of the document hold a table
+pub(crate) struct Block {
+    pub heading: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn parse_document(contents: &str) -> Result<Vec<Decision>, String> {
+    let lines: Vec<&str> = contents.lines().map(str::trim).collect();
+    let mut decisions: Vec<Decision> = vec![];
+
+    for block in table_blocks(&lines) {
+        if !is_decision_table(&lines[block.start..block.end]) {
+            continue;
+        }
+
+        let name = block
+            .heading
+            .unwrap_or_else(|| format!("table_{}", decisions.len() + 1));
+        if decisions.iter().any(|d| d.name == name) {
+            return Err(format!(
+                "line {}: duplicate decision name \"{}\"",
+                block.start + 1,
+                name
+            ));
+        }
+
+        let table = parse(&lines[block.start..block.end].join("\n"))
+            .map_err(|e| format!("decision \"{}\" at line {}: {}", name, block.start + 1, e))?;
+        decisions.push(Decision { name, table });
+    }
+
+    Ok(decisions)
+}
+
+/// The document with every decision table rewritten by `to_markdown`, everything else untouched
+pub fn format_document(contents: &str) -> Result<String, String> {
+    let original: Vec<&str> = contents.lines().collect();
+    let lines: Vec<&str> = original.iter().map(|l| l.trim()).collect();
+    let mut formatted: Vec<String> = vec![];
+    let mut copied = 0;
+
+    for block in table_blocks(&lines) {
+        if !is_decision_table(&lines[block.start..block.end]) {
+            continue;
+        }
+        let table = parse(&lines[block.start..block.end].join("\n"))
+            .map_err(|e| format!("table at line {}: {}", block.start + 1, e))?;
+
+        formatted.extend(original[copied..block.start].iter().map(|l| l.to_string()));
+        formatted.extend(to_markdown(&table).lines().map(str::to_string));
+        copied = block.end;
+    }
+    formatted.extend(original[copied..].iter().map(|l| l.to_string()));
+
+    let mut result = formatted.join("\n");
+    if contents.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// A document with every decision as a heading followed by its table
+pub fn write_document(decisions: &[Decision]) -> String {
+    decisions
+        .iter()
+        .map(|d| format!("## {}\n\n{}", d.name, to_markdown(&d.table)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub(crate) fn table_blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = vec![];
+    let mut heading: Option<String> = None;
+    let mut fence: Option<&str> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(marker) = fence {
+            if line.starts_with(marker) {
+                fence = None;
+            }
+            i += 1;
+            continue;
+        }
+        if line.starts_with("```") || line.starts_with("~~~") {
+            fence = Some(&line[..3]);
+            i += 1;
+            continue;
+        }
+        if let Some(text) = heading_text(line) {
+            heading = Some(text);
+            i += 1;
+            continue;
+        }
+
+        let starts_table =
+            line.contains('|') && lines.get(i + 1).is_some_and(|next| is_delimiter_row(next));
+        if !starts_table {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < lines.len() && !lines[i].is_empty() && lines[i].contains('|') {
+            i += 1;
+        }
+        blocks.push(Block {
+            heading: heading.take(),
+            start,
+            end: i,
+        });
+    }
+
+    blocks
+}
+
+/// `## Dish` and `## Dish ##` are both named "Dish"
+fn heading_text(line: &str) -> Option<String> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let text = rest.trim().trim_end_matches('#').trim();
+    Some(text.to_string())
+}
+
+/// The 4th row holds `##` in every cell but the first, which may name the hit policy
+pub(crate) fn is_decision_table(table_lines: &[&str]) -> bool {
+    table_lines.get(3).is_some_and(|line| {
+        let cells = split_row(line);
+        cells.len() > 1 && cells[1..].iter().all(|cell| cell == "##")
+    })
+}
+
+fn is_delimiter_row(line: &str) -> bool {
+    if !line.contains('-') {
+        return false;
+    }
+
+    split_row(line).iter().all(|cell| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression_parser::operand::Operand;
+    use crate::logic_table::run_table;
+    use serde_json::Value;
+    use std::fs;
+
+    #[test]
+    fn parses_tables_in_document() -> Result<(), String> {
+        let contents = fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+
+        let decisions = parse_document(&contents)?;
+        let names: Vec<&str> = decisions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["Dish", "Beverage"]);
+        assert_eq!(decisions[0].table.rows.len(), 3);
+        assert_eq!(decisions[1].table.defs.inputs[0].0, "desiredDish");
+
+        let context = serde_json::json!({ "season": "Fall", "guestCount": 10 });
+        let res = run_table(&decisions[0].table, &context)?;
+        assert_eq!(
+            res[0].get("desiredDish"),
+            Some(&Operand::Primitive(Value::String("Stew".to_owned())))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn names_tables_without_heading() -> Result<(), String> {
+        let contents = r#"
+Some prose first.
+
+| a      | b      |
+|--------|-------:|
+| number | number |
+| ##     | ##     |
+| 1      | 2      |
+"#;
+
+        let decisions = parse_document(contents)?;
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].name, "table_1");
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_duplicate_names_and_broken_tables() {
+        let table = "| a | b |\n|---|--:|\n| number | number |\n| ## | ## |\n| 1 | 2 |\n";
+        let duplicate = format!("## Same\n{}\n## Same\n{}", table, table);
+        let err = parse_document(&duplicate).unwrap_err();
+        assert!(err.contains("duplicate decision name \"Same\""), "{}", err);
+
+        let broken = "## Broken\n| a | b |\n|---|--:|\n| number | number |\n| ## | ## |\n| 1 |\n";
+        let err = parse_document(broken).unwrap_err();
+        assert!(err.starts_with("decision \"Broken\" at line 2"), "{}", err);
+    }
+
+    #[test]
+    fn skips_ordinary_tables() -> Result<(), String> {
+        let contents = "## Prices\n\n| item | price |\n|------|------:|\n| ribs | 12 |\n| stew | 9 |\n| ale | 4 |\n\n## Dish\n| season | dish |\n|---|--:|\n| string | string |\n| ## | ## |\n| \"Fall\" | \"Stew\" |\n";
+
+        let names: Vec<String> = parse_document(contents)?
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, vec!["Dish"]);
+        assert!(format_document(contents)?.contains("| ribs | 12 |"));
+        Ok(())
+    }
+
+    #[test]
+    fn formats_tables_in_place() -> Result<(), String> {
+        let contents = "# Doc\n\nSome | prose.\n\n## Dish\n  season|dish\n  ---|--:\n  string|string\n  ##|##\n  \"Fall\"|\"Stew\"\n\nThe end.\n";
+
+        let formatted = format_document(contents)?;
+        assert_eq!(
+            formatted,
+            "# Doc\n\nSome | prose.\n\n## Dish\n| season |   dish |\n|--------|-------:|\n| string | string |\n| ##     |     ## |\n| \"Fall\" | \"Stew\" |\n\nThe end.\n"
+        );
+        assert_eq!(format_document(&formatted)?, formatted);
+
+        let sample = fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+        let names: Vec<String> = parse_document(&format_document(&sample)?)?
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, vec!["Dish", "Beverage"]);
+        Ok(())
+    }
+
+    #[test]
+    fn writes_decisions_as_document() -> Result<(), String> {
+        let sample = fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+        let decisions = parse_document(&sample)?;
+
+        let written = write_document(&decisions);
+        assert!(written.starts_with("## Dish\n\n| season "), "{}", written);
+        let reparsed = parse_document(&written)?;
+        assert_eq!(reparsed.len(), 2);
+        assert_eq!(reparsed[1].name, "Beverage");
+        assert_eq!(reparsed[1].table, decisions[1].table);
+        Ok(())
+    }
+}
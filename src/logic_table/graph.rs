@@ -0,0 +1,242 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::document::Decision;
+use super::{input_variables, run_table};
+use crate::expression_parser::operand::Operand;
+
+/*
+    Decision requirements graph - a decision whose input column reads another
+    decision's output requires that decision. Required decisions run
+    first, in dependency order, and their outputs are merged into the context.
+*/
+
+#[derive(Debug)]
+pub struct DecisionGraph {
+    decisions: Vec<Decision>,
+    /// indexes of the decisions each decision requires
+    requires: Vec<Vec<usize>>,
+}
+
+impl DecisionGraph {
+    pub fn new(decisions: Vec<Decision>) -> Result<Self, String> {
+        let mut producers: HashMap<&str, usize> = HashMap::new();
+        for (index, decision) in decisions.iter().enumerate() {
+            if decisions[..index].iter().any(|d| d.name == decision.name) {
+                return Err(format!("duplicate decision name \"{}\"", decision.name));
+            }
+
+            for (output, _) in decision.table.defs.outputs.iter() {
+                if let Some(other) = producers.insert(output, index) {
+                    return Err(format!(
+                        "output \"{}\" is produced by both \"{}\" and \"{}\"",
+                        output, decisions[other].name, decision.name
+                    ));
+                }
+            }
+        }
+
+        let requires = decisions
+            .iter()
+            .enumerate()
+            .map(|(index, decision)| {
+                let mut required: Vec<usize> = vec![];
+                for (input, _) in decision.table.defs.inputs.iter() {
+                    // a header like `order-id` may name an output as it is
+                    let header = std::iter::once(input.clone());
+                    for variable in header.chain(input_variables(input)) {
+                        match producers.get(variable.as_str()) {
+                            Some(&p) if p != index && !required.contains(&p) => required.push(p),
+                            _ => {}
+                        }
+                    }
+                }
+                required
+            })
+            .collect();
+
+        let graph = DecisionGraph {
+            decisions,
+            requires,
+        };
+        for index in 0..graph.decisions.len() {
+            graph.order_for(index)?;
+        }
+
+        Ok(graph)
+    }
+
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    /// Names of the decisions `name` needs, in evaluation order, ending with `name`
+    pub fn order(&self, name: &str) -> Result<Vec<&str>, String> {
+        let order = self.order_for(self.index_of(name)?)?;
+        Ok(order
+            .into_iter()
+            .map(|i| self.decisions[i].name.as_str())
+            .collect())
+    }
+
+    /// Runs `name` after everything it requires, each required decision adds its first
+    /// matching row to the context - or null outputs when no row matches
+    pub fn evaluate(
+        &self,
+        name: &str,
+        context: &Value,
+    ) -> Result<Vec<HashMap<String, Operand>>, String> {
+        let order = self.order_for(self.index_of(name)?)?;
+        let Some((&target, required)) = order.split_last() else {
+            return Ok(vec![]);
+        };
+
+        let mut context = match context {
+            Value::Object(_) => context.clone(),
+            Value::Null => Value::Object(Default::default()),
+            _ => return Err("decision context has to be an object".to_string()),
+        };
+
+        for &index in required {
+            self.merge_outputs(index, &mut context)?;
+        }
+
+        let decision = &self.decisions[target];
+        run_table(&decision.table, &context)
+            .map_err(|e| format!("decision \"{}\": {}", decision.name, e))
+    }
+
+    /// Evaluates `name` and merges the outputs of its first matching row into `context`
+    pub fn evaluate_into(&self, name: &str, context: &mut Value) -> Result<(), String> {
+        let order = self.order_for(self.index_of(name)?)?;
+        for index in order {
+            self.merge_outputs(index, context)?;
+        }
+
+        Ok(())
+    }
+
+    fn merge_outputs(&self, index: usize, context: &mut Value) -> Result<(), String> {
+        let decision = &self.decisions[index];
+        let results = run_table(&decision.table, context)
+            .map_err(|e| format!("decision \"{}\": {}", decision.name, e))?;
+
+        let Value::Object(values) = context else {
+            return Err("decision context has to be an object".to_string());
+        };
+        for (output, _) in decision.table.defs.outputs.iter() {
+            let value = results
+                .first()
+                .and_then(|r| r.get(output))
+                .map(Operand::to_value)
+                .unwrap_or(Value::Null);
+            values.insert(output.clone(), value);
+        }
+
+        Ok(())
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize, String> {
+        self.decisions
+            .iter()
+            .position(|d| d.name == name)
+            .ok_or_else(|| format!("unknown decision \"{}\"", name))
+    }
+
+    /// Depth first topological order, `path` is the chain being visited to report cycles
+    fn order_for(&self, index: usize) -> Result<Vec<usize>, String> {
+        let mut order: Vec<usize> = vec![];
+        let mut path: Vec<usize> = vec![];
+        self.visit(index, &mut path, &mut order)?;
+        Ok(order)
+    }
+
+    fn visit(
+        &self,
+        index: usize,
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        if order.contains(&index) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|&p| p == index) {
+            let mut cycle: Vec<&str> = path[start..]
+                .iter()
+                .map(|&p| self.decisions[p].name.as_str())
+                .collect();
+            cycle.push(&self.decisions[index].name);
+            return Err(format!("decision cycle: {}", cycle.join(" -> ")));
+        }
+
+        path.push(index);
+        for &required in self.requires[index].iter() {
+            self.visit(required, path, order)?;
+        }
+        path.pop();
+
+        order.push(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::document::parse_document;
+    use std::fs;
+
+    fn get_test_graph() -> Result<DecisionGraph, String> {
+        let contents = fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+
+        DecisionGraph::new(parse_document(&contents)?)
+    }
+
+    #[test]
+    fn evaluates_required_decisions_first() -> Result<(), String> {
+        let graph = get_test_graph()?;
+        assert_eq!(graph.order("Beverage")?, vec!["Dish", "Beverage"]);
+        assert_eq!(graph.order("Dish")?, vec!["Dish"]);
+
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 4 });
+        let res = graph.evaluate("Beverage", &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("beverage"),
+            Some(&Operand::Primitive(Value::String("Wine".to_owned())))
+        );
+
+        // no dish matches, so nothing to drink either
+        let context = serde_json::json!({ "season": "Spring", "guestCount": 4 });
+        assert!(graph.evaluate("Beverage", &context)?.is_empty());
+
+        assert!(graph.evaluate("Dessert", &context).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn detects_cycles() -> Result<(), String> {
+        let contents = r#"
+## Ping
+
+| pong   | ping   |
+|--------|-------:|
+| number | number |
+| ##     | ##     |
+| 1      | 2      |
+
+## Pong
+
+| ping   | pong   |
+|--------|-------:|
+| number | number |
+| ##     | ##     |
+| 2      | 1      |
+"#;
+
+        let err = DecisionGraph::new(parse_document(contents)?).unwrap_err();
+        assert_eq!(err, "decision cycle: Ping -> Pong -> Ping");
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use serde_json::Value;
+
+use super::document::Decision;
+use super::graph::DecisionGraph;
+use super::trace::{run_table_traced, CellOutcome, TableTrace};
+use super::xml::escape;
+use super::{hit_policy_name, Table};
+
+/*
+    Standalone HTML pages for support staff. Inputs are blue, outputs green,
+    the hit policy is a badge next to the name. Rendered with a context, the
+    matching rules are highlighted and every input cell shows the value it was
+    tested against and how the test ended.
+*/
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; font-family: monospace; }
+th { font-family: sans-serif; }
+.type { display: block; font-weight: normal; font-size: 0.8em; color: #666; }
+.input { background: #eaf2ff; }
+.output { background: #eafbe7; text-align: right; }
+.doc { color: #555; font-family: sans-serif; }
+.badge { padding: 0.1em 0.6em; border-radius: 1em; background: #444; color: #fff; font-size: 0.7em; vertical-align: middle; }
+tr.matched td { background: #fff1a8; font-weight: bold; }
+td.not-matched { color: #b00020; text-decoration: line-through; }
+td.skipped { color: #aaa; }
+pre { background: #f6f6f6; padding: 0.8em; }
+";
+
+/// A page showing the table
+pub fn to_html(name: &str, table: &Table) -> String {
+    page(name, &render_table(name, table, None))
+}
+
+/// A page showing the table and how it decided for the context
+pub fn to_html_with_context(name: &str, table: &Table, context: &Value) -> Result<String, String> {
+    let (_, trace) = run_table_traced(table, context)?;
+    let body = render_table(name, table, Some(&trace)) + &render_context(context);
+    Ok(page(name, &body))
+}
+
+/// A page showing every decision of a document, evaluated for the context when there is one
+pub fn document_to_html(
+    title: &str,
+    decisions: Vec<Decision>,
+    context: Option<&Value>,
+) -> Result<String, String> {
+    let mut body = String::new();
+    let Some(context) = context else {
+        for decision in decisions.iter() {
+            body.push_str(&render_table(&decision.name, &decision.table, None));
+        }
+        return Ok(page(title, &body));
+    };
+
+    // a decision sees the outputs of the decisions it requires, like `DecisionGraph::evaluate`
+    let graph = DecisionGraph::new(decisions)?;
+    for decision in graph.decisions() {
+        let mut merged = match context {
+            Value::Null => Value::Object(Default::default()),
+            _ => context.clone(),
+        };
+        graph.evaluate_into(&decision.name, &mut merged)?;
+        let (_, trace) = run_table_traced(&decision.table, &merged)
+            .map_err(|e| format!("decision \"{}\": {}", decision.name, e))?;
+        body.push_str(&render_table(&decision.name, &decision.table, Some(&trace)));
+    }
+    body.push_str(&render_context(context));
+
+    Ok(page(title, &body))
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn render_table(name: &str, table: &Table, trace: Option<&TableTrace>) -> String {
+    let defs = &table.defs;
+    let mut out = format!("<h1>{}", escape(name));
+    if let Some(policy) = &defs.hit_policy {
+        let title = hit_policy_name(policy).unwrap_or(policy);
+        out.push_str(&format!(
+            " <span class=\"badge\" title=\"hit policy {}\">{}</span>",
+            escape(title),
+            escape(policy)
+        ));
+    }
+    out.push_str("</h1>\n<table>\n<thead>\n<tr><th>#</th>");
+
+    if let Some(header) = &defs.rule_id {
+        out.push_str(&format!("<th class=\"doc\">{}</th>", escape(header)));
+    }
+    for (class, columns) in [("input", &defs.inputs), ("output", &defs.outputs)] {
+        for (header, var_type) in columns.iter() {
+            out.push_str(&format!(
+                "<th class=\"{}\">{}<span class=\"type\">{}</span></th>",
+                class,
+                escape(header),
+                escape(var_type)
+            ));
+        }
+    }
+    if let Some(header) = &defs.annotation {
+        out.push_str(&format!("<th class=\"doc\">{}</th>", escape(header)));
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    for (row_index, row) in table.rows.iter().enumerate() {
+        let rule_trace = trace.and_then(|t| t.rules.iter().find(|r| r.row == row_index));
+        let matched = rule_trace.is_some_and(|r| r.matched);
+        out.push_str(if matched {
+            "<tr class=\"matched\">"
+        } else {
+            "<tr>"
+        });
+        out.push_str(&format!("<td>{}</td>", row_index + 1));
+
+        if defs.rule_id.is_some() {
+            out.push_str(&doc_cell(&row.id));
+        }
+        for (col_index, cell) in row.cells.iter().enumerate() {
+            if col_index >= defs.inputs.len() {
+                out.push_str(&format!("<td class=\"output\">{}</td>", escape(cell)));
+                continue;
+            }
+
+            let cell_trace = rule_trace.and_then(|r| r.cells.get(col_index));
+            match cell_trace {
+                Some(cell_trace) => {
+                    let (class, outcome) = match cell_trace.outcome {
+                        CellOutcome::Matched => ("input matched", "matched"),
+                        CellOutcome::NotMatched => ("input not-matched", "not matched"),
+                        CellOutcome::Skipped => ("input skipped", "skipped"),
+                    };
+                    out.push_str(&format!(
+                        "<td class=\"{}\" title=\"{} = {}: {}\">{}</td>",
+                        class,
+                        escape(&cell_trace.column),
+                        escape(&cell_trace.input.to_string()),
+                        outcome,
+                        escape(cell)
+                    ));
+                }
+                None => out.push_str(&format!("<td class=\"input\">{}</td>", escape(cell))),
+            }
+        }
+        if defs.annotation.is_some() {
+            out.push_str(&doc_cell(&row.annotation));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n");
+
+    if let Some(trace) = trace {
+        out.push_str(&render_trace(trace));
+    }
+    out
+}
+
+fn doc_cell(cell: &Option<String>) -> String {
+    format!(
+        "<td class=\"doc\">{}</td>",
+        escape(cell.as_deref().unwrap_or(""))
+    )
+}
+
+/// The input values and the rules that matched
+fn render_trace(trace: &TableTrace) -> String {
+    let mut out = String::from("<table>\n<tr><th>input</th><th>value</th></tr>\n");
+    if let Some(first) = trace.rules.first() {
+        for cell in first.cells.iter() {
+            out.push_str(&format!(
+                "<tr><td class=\"input\">{}</td><td>{}</td></tr>\n",
+                escape(&cell.column),
+                escape(&cell.input.to_string())
+            ));
+        }
+    }
+    out.push_str("</table>\n");
+
+    let matched: Vec<String> = trace
+        .rules
+        .iter()
+        .filter(|r| r.matched)
+        .map(|r| match &r.id {
+            Some(id) => format!("{} ({})", r.row + 1, escape(id)),
+            None => (r.row + 1).to_string(),
+        })
+        .collect();
+    let summary = match matched.len() {
+        0 => "No rule matched".to_string(),
+        1 => format!("Rule {} matched", matched[0]),
+        _ => format!("Rules {} matched", matched.join(", ")),
+    };
+    out.push_str(&format!("<p>{}</p>\n", summary));
+    out
+}
+
+fn render_context(context: &Value) -> String {
+    let json = serde_json::to_string_pretty(context).unwrap_or_default();
+    format!("<h2>Context</h2>\n<pre>{}</pre>\n", escape(&json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::parse;
+
+    fn get_test_table() -> Result<Table, String> {
+        let contents = r#"
+        | rule | season   | guestCount | desiredDish | notes      |
+        |:-----|----------|------------|------------:|:-----------|
+        | id   | string   | number     |      string | string     |
+        | F    | ##       | ##         |          ## | ##         |
+        | R-1  | "Fall"   | <= 8       | "Spaceribs" | small <3   |
+        | R-2  | "Winter" | <= 8       | "Roastbeef" |            |
+        "#;
+        parse(contents)
+    }
+
+    #[test]
+    fn renders_table_page() -> Result<(), String> {
+        let html = to_html("Dish", &get_test_table()?);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Dish</title>"));
+        assert!(html.contains("<span class=\"badge\" title=\"hit policy FIRST\">F</span>"));
+        assert!(html.contains("<th class=\"input\">season<span class=\"type\">string</span></th>"));
+        assert!(html.contains("<td class=\"output\">&quot;Spaceribs&quot;</td>"));
+        assert!(html.contains("<td class=\"doc\">small &lt;3</td>"));
+        assert!(!html.contains("class=\"matched\""));
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_matched_rules() -> Result<(), String> {
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 4 });
+        let html = to_html_with_context("Dish", &get_test_table()?, &context)?;
+
+        assert!(html.contains("<tr class=\"matched\"><td>2</td>"));
+        assert!(html.contains(
+            "<td class=\"input not-matched\" title=\"season = &quot;Winter&quot;: not matched\">&quot;Fall&quot;</td>"
+        ));
+        assert!(html.contains("<td class=\"input skipped\""));
+        assert!(html.contains("<p>Rule 2 (R-2) matched</p>"));
+        assert!(html.contains("<h2>Context</h2>"));
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_rules_of_required_decisions() -> Result<(), String> {
+        let contents = std::fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+        let decisions = crate::logic_table::document::parse_document(&contents)?;
+        let context = serde_json::json!({ "season": "Fall", "guestCount": 4 });
+
+        let html = document_to_html("decisions", decisions, Some(&context))?;
+        let beverage = &html[html.find("<h1>Beverage").unwrap()..];
+        assert!(
+            beverage.contains("<tr class=\"matched\"><td>1</td>"),
+            "{}",
+            beverage
+        );
+        assert!(beverage.contains("<p>Rule 1 matched</p>"), "{}", beverage);
+        assert!(!html.contains("No rule matched"));
+        Ok(())
+    }
+}
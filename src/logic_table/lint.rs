@@ -0,0 +1,457 @@
+use serde_json::Value;
+use std::fmt;
+
+use super::{hit_policy_name, is_wildcard, Table};
+use crate::expression_parser::operand::{Operand, Operator};
+use crate::expression_parser::tokenizer::Tokenizer;
+
+/*
+    Static checks over the input cells of a table. Cells are understood when
+    they are a wildcard (`-`), a comparison with a number (`< 10`, `>= 2.5`, `8`)
+    or a single value (`"Fall"`, `true`). Rules with any other cell are left out.
+
+    Every numeric column is cut at the numbers its cells mention, value columns
+    are split into the values mentioned - plus "any other value" when a cell
+    of the column is a wildcard - and each combination of those pieces is
+    checked against the rules that cover it. The hit policy of the table
+    decides between overlaps (UNIQUE) and shadowed rules (FIRST), other
+    policies - and tables without one, which return every match - are only
+    checked for gaps.
+*/
+
+/// Upper bound of input combinations checked, larger tables are not linted
+const MAX_COMBINATIONS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HitPolicy {
+    /// at most one rule may match any input
+    Unique,
+    /// the first matching rule wins
+    First,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Lint {
+    /// two rules match the same input, only reported under UNIQUE
+    Overlap {
+        rules: (usize, usize),
+        inputs: Vec<String>,
+    },
+    /// no rule matches these inputs
+    Gap { inputs: Vec<String> },
+    /// every input of `rule` is matched by earlier rules first, only reported under FIRST
+    Shadowed { rule: usize },
+    /// the cells of `rule` are not understood, the rule is not checked
+    Skipped { rule: usize },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // rules are counted from 1, like the rows a reader sees
+        match self {
+            Lint::Overlap { rules, inputs } => write!(
+                f,
+                "rules {} and {} overlap for {}",
+                rules.0 + 1,
+                rules.1 + 1,
+                inputs.join(", ")
+            ),
+            Lint::Gap { inputs } => write!(f, "no rule matches {}", inputs.join(", ")),
+            Lint::Shadowed { rule } => {
+                write!(
+                    f,
+                    "rule {} is never used, earlier rules match first",
+                    rule + 1
+                )
+            }
+            Lint::Skipped { rule } => write!(f, "rule {} is too complex to check", rule + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Any,
+    /// numbers between the bounds, a bound is (value, inclusive)
+    Range {
+        lower: Option<(f64, bool)>,
+        upper: Option<(f64, bool)>,
+    },
+    Is(Value),
+}
+
+impl Condition {
+    fn contains_number(&self, n: f64) -> bool {
+        match self {
+            Condition::Any => true,
+            Condition::Range { lower, upper } => {
+                let above = match lower {
+                    Some((l, true)) => n >= *l,
+                    Some((l, false)) => n > *l,
+                    None => true,
+                };
+                let below = match upper {
+                    Some((u, true)) => n <= *u,
+                    Some((u, false)) => n < *u,
+                    None => true,
+                };
+                above && below
+            }
+            Condition::Is(_) => false,
+        }
+    }
+}
+
+/// One piece of a column's domain
+#[derive(Debug, Clone)]
+enum Piece {
+    /// a representative number and the description of the piece, e.g. "> 8"
+    Number(f64, String),
+    Value(Value),
+    /// a value no cell of the column mentions
+    OtherValue,
+}
+
+impl Piece {
+    fn covered_by(&self, condition: &Condition) -> bool {
+        match (self, condition) {
+            (_, Condition::Any) => true,
+            (Piece::Number(n, _), c) => c.contains_number(*n),
+            (Piece::Value(v), Condition::Is(w)) => v == w,
+            _ => false,
+        }
+    }
+
+    fn describe(&self, column: &str) -> String {
+        match self {
+            Piece::Number(_, description) => format!("{} {}", column, description),
+            Piece::Value(v) => format!("{} = {}", column, v),
+            Piece::OtherValue => format!("{} = any other value", column),
+        }
+    }
+}
+
+impl HitPolicy {
+    /// The policy of `table` if it is linted beyond gaps
+    fn of(table: &Table) -> Option<HitPolicy> {
+        match table.defs.hit_policy.as_deref().and_then(hit_policy_name) {
+            Some("UNIQUE") => Some(HitPolicy::Unique),
+            Some("FIRST") => Some(HitPolicy::First),
+            _ => None,
+        }
+    }
+}
+
+pub fn lint(table: &Table) -> Result<Vec<Lint>, String> {
+    let hit_policy = HitPolicy::of(table);
+    let mut lints: Vec<Lint> = vec![];
+
+    // conditions[rule][column] of the rules that are understood
+    let mut rules: Vec<(usize, Vec<Condition>)> = vec![];
+    for (index, row) in table.rows.iter().enumerate() {
+        let conditions: Option<Vec<Condition>> = (0..table.defs.inputs.len())
+            .map(|col| condition(&row.cells[col]))
+            .collect();
+        match conditions {
+            Some(conditions) => rules.push((index, conditions)),
+            None => lints.push(Lint::Skipped { rule: index }),
+        }
+    }
+
+    let columns: Vec<Vec<Piece>> = table
+        .defs
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(col, (_, var_type))| pieces(rules.iter().map(|r| &r.1[col]), var_type))
+        .collect();
+
+    let combinations = columns
+        .iter()
+        .try_fold(1usize, |total, pieces| {
+            total.checked_mul(pieces.len().max(1))
+        })
+        .filter(|total| *total <= MAX_COMBINATIONS)
+        .ok_or_else(|| "table has too many input combinations to lint".to_string())?;
+
+    let gaps_known = lints.is_empty();
+    let mut overlaps: Vec<(usize, usize)> = vec![];
+    // rules that match at least one input before any earlier rule does
+    let mut used: Vec<bool> = vec![false; rules.len()];
+
+    let mut choice = vec![0usize; columns.len()];
+    for _ in 0..combinations {
+        let covering: Vec<usize> = (0..rules.len())
+            .filter(|&r| {
+                columns
+                    .iter()
+                    .zip(choice.iter())
+                    .zip(rules[r].1.iter())
+                    .all(|((pieces, &c), condition)| {
+                        pieces.get(c).is_none_or(|p| p.covered_by(condition))
+                    })
+            })
+            .collect();
+
+        let describe = || -> Vec<String> {
+            columns
+                .iter()
+                .zip(choice.iter())
+                .zip(table.defs.inputs.iter())
+                .filter_map(|((pieces, &c), (name, _))| pieces.get(c).map(|p| p.describe(name)))
+                .collect()
+        };
+
+        match covering.as_slice() {
+            [] if gaps_known => lints.push(Lint::Gap { inputs: describe() }),
+            [] => {}
+            [first, ..] => {
+                used[*first] = true;
+                if hit_policy == Some(HitPolicy::Unique) {
+                    for (i, a) in covering.iter().enumerate() {
+                        for b in covering[i + 1..].iter() {
+                            let pair = (rules[*a].0, rules[*b].0);
+                            if !overlaps.contains(&pair) {
+                                overlaps.push(pair);
+                                lints.push(Lint::Overlap {
+                                    rules: pair,
+                                    inputs: describe(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // next combination, the last column changes fastest
+        for col in (0..choice.len()).rev() {
+            choice[col] += 1;
+            if choice[col] < columns[col].len() {
+                break;
+            }
+            choice[col] = 0;
+        }
+    }
+
+    if hit_policy == Some(HitPolicy::First) {
+        for (r, (index, _)) in rules.iter().enumerate() {
+            if !used[r] {
+                lints.push(Lint::Shadowed { rule: *index });
+            }
+        }
+    }
+
+    Ok(lints)
+}
+
+fn condition(cell: &str) -> Option<Condition> {
+    if is_wildcard(cell) {
+        return Some(Condition::Any);
+    }
+
+    let mut parser = Tokenizer::new(cell);
+    parser.parse().ok()?;
+
+    let (operator, operand) = match parser.operands() {
+        [Operand::Primitive(v)] => (Operator::E, v),
+        [Operand::OperatorToken(o), Operand::Primitive(v)] => (o.clone(), v),
+        _ => return None,
+    };
+
+    if let Value::Number(n) = operand {
+        let n = n.as_f64()?;
+        let (lower, upper) = match operator {
+            Operator::E => (Some((n, true)), Some((n, true))),
+            Operator::L => (None, Some((n, false))),
+            Operator::LE => (None, Some((n, true))),
+            Operator::G => (Some((n, false)), None),
+            Operator::GE => (Some((n, true)), None),
+            _ => return None,
+        };
+        return Some(Condition::Range { lower, upper });
+    }
+
+    match (operator, operand) {
+        (Operator::E, v @ (Value::String(_) | Value::Bool(_))) => Some(Condition::Is(v.clone())),
+        _ => None,
+    }
+}
+
+/// Splits a column into pieces that every condition either covers fully or not at all
+fn pieces<'a>(conditions: impl Iterator<Item = &'a Condition>, var_type: &str) -> Vec<Piece> {
+    let mut bounds: Vec<f64> = vec![];
+    let mut values: Vec<Value> = vec![];
+    let mut wildcard = false;
+    if var_type == "boolean" {
+        values = vec![Value::Bool(true), Value::Bool(false)];
+    }
+
+    for condition in conditions {
+        match condition {
+            Condition::Range { lower, upper } => {
+                bounds.extend(lower.iter().chain(upper.iter()).map(|(b, _)| *b));
+            }
+            Condition::Is(v) if !values.contains(v) => values.push(v.clone()),
+            Condition::Any => wildcard = true,
+            _ => {}
+        }
+    }
+
+    let enumerated = !values.is_empty();
+    let mut pieces: Vec<Piece> = values.into_iter().map(Piece::Value).collect();
+    if enumerated && wildcard && var_type != "boolean" {
+        pieces.push(Piece::OtherValue);
+    }
+    if bounds.is_empty() {
+        return pieces;
+    }
+
+    bounds.sort_by(|a, b| a.total_cmp(b));
+    bounds.dedup();
+
+    pieces.push(Piece::Number(bounds[0] - 1.0, format!("< {}", bounds[0])));
+    for (i, b) in bounds.iter().enumerate() {
+        pieces.push(Piece::Number(*b, format!("= {}", b)));
+        match bounds.get(i + 1) {
+            Some(next) => pieces.push(Piece::Number(
+                (b + next) / 2.0,
+                format!("> {} and < {}", b, next),
+            )),
+            None => pieces.push(Piece::Number(b + 1.0, format!("> {}", b))),
+        }
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::parse;
+
+    fn table(hit_policy: &str, rules: &str) -> Result<Table, String> {
+        let header = format!(
+            r#"
+        | season   | guestCount | dish   |
+        |----------|------------|-------:|
+        | string   | number     | string |
+        | {:<8} | ##         |     ## |
+        "#,
+            hit_policy
+        );
+        parse(&format!("{}{}", header, rules))
+    }
+
+    #[test]
+    fn finds_overlaps_under_unique() -> Result<(), String> {
+        let table = table(
+            "U",
+            r#"
+        | "Fall"   | <= 8       | "Ribs" |
+        | "Fall"   | >= 8       | "Stew" |
+        | "Winter" | -          | "Beef" |
+        "#,
+        )?;
+
+        let lints = lint(&table)?;
+        assert_eq!(
+            lints,
+            vec![Lint::Overlap {
+                rules: (0, 1),
+                inputs: vec![
+                    "season = \"Fall\"".to_string(),
+                    "guestCount = 8".to_string()
+                ],
+            },]
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "rules 1 and 2 overlap for season = \"Fall\", guestCount = 8"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn finds_gaps() -> Result<(), String> {
+        let table = table(
+            "##",
+            r#"
+        | "Fall"   | < 8        | "Ribs" |
+        | "Fall"   | > 8        | "Stew" |
+        | "Winter" | -          | "Beef" |
+        "#,
+        )?;
+
+        let lints = lint(&table)?;
+        assert_eq!(
+            lints,
+            vec![Lint::Gap {
+                inputs: vec![
+                    "season = \"Fall\"".to_string(),
+                    "guestCount = 8".to_string()
+                ],
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn finds_shadowed_rules_under_first() -> Result<(), String> {
+        let rules = r#"
+        | "Fall"   | -          | "Ribs" |
+        | "Fall"   | > 8        | "Stew" |
+        | "Winter" | -          | "Beef" |
+        | -        | < 0        | "None" |
+        "#;
+
+        let lints = lint(&table("F", rules)?)?;
+        let shadowed: Vec<&Lint> = lints
+            .iter()
+            .filter(|l| matches!(l, Lint::Shadowed { .. }))
+            .collect();
+        assert_eq!(shadowed, vec![&Lint::Shadowed { rule: 1 }]);
+
+        // the wildcard season stands for seasons without a rule of their own
+        assert!(lints.contains(&Lint::Gap {
+            inputs: vec![
+                "season = any other value".to_string(),
+                "guestCount > 8".to_string()
+            ],
+        }));
+
+        // overlaps are fine under FIRST, but not under UNIQUE
+        let unique = lint(&table("U", rules)?)?;
+        let overlaps: Vec<(usize, usize)> = unique
+            .iter()
+            .filter_map(|l| match l {
+                Lint::Overlap { rules, .. } => Some(*rules),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(overlaps, vec![(0, 3), (0, 1), (2, 3)]);
+
+        // other policies only have gaps, like tables without one
+        for policy in ["C", "##"] {
+            let collect = lint(&table(policy, rules)?)?;
+            assert!(collect.iter().all(|l| matches!(l, Lint::Gap { .. })));
+            assert!(!collect.is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn skips_rules_it_does_not_understand() -> Result<(), String> {
+        let table = table(
+            "##",
+            r#"
+        | starts with(?, "F") | -    | "Ribs" |
+        | "Winter"            | -    | "Beef" |
+        "#,
+        )?;
+
+        let lints = lint(&table)?;
+        assert_eq!(lints, vec![Lint::Skipped { rule: 0 }]);
+        Ok(())
+    }
+}
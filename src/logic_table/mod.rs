@@ -1,61 +1,130 @@
-use crate::context::var_to_operand;
-use crate::expression_parser::executor::interpret;
+pub mod coverage;
+pub mod csv;
+pub mod dmn;
+pub mod document;
+pub mod graph;
+pub mod html;
+pub mod lint;
+pub mod test_cases;
+pub mod trace;
+pub mod validate;
+pub mod writer;
+mod xml;
+
+use crate::context::{get_context_var, var_to_operand};
+use crate::expression_parser::decimal::{Decimal, NumberMode};
+use crate::expression_parser::executor::interpret_with_mode;
 use crate::expression_parser::operand::{Operand, Operator};
+use crate::expression_parser::temporal::Temporal;
 use crate::expression_parser::tokenizer::Tokenizer;
 use serde_json::Value;
+use trace::{CellOutcome, CellTrace, RuleTrace, TableTrace};
 
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Definition {
     pub inputs: Vec<(String, String)>,
     pub outputs: Vec<(String, String)>,
+    /// header of the rule id column - a `:---` column of type `id`
+    pub rule_id: Option<String>,
+    /// header of the annotation column - any other `:---` column
+    pub annotation: Option<String>,
+    /// first cell of the 4th definition row, e.g. `U` or `FIRST` - `##` when not set
+    pub hit_policy: Option<String>,
+}
+
+impl Definition {
+    /// Rule id and annotation go into the results next to the outputs, so their headers have to differ
+    pub(crate) fn check_result_headers(&self) -> Result<(), String> {
+        let documentation = [(&self.rule_id, "rule id"), (&self.annotation, "annotation")];
+        for (header, kind) in documentation {
+            let Some(header) = header else {
+                continue;
+            };
+            if self.outputs.iter().any(|(output, _)| output == header) {
+                return Err(format!(
+                    "{} column \"{}\" has the name of an output",
+                    kind, header
+                ));
+            }
+        }
+        if self.rule_id.is_some() && self.rule_id == self.annotation {
+            return Err("rule id and annotation columns have the same name".to_string());
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Table {
     pub rows: Vec<Row>,
     pub defs: Definition,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Row {
+    /// input cells followed by output cells
     pub cells: Vec<String>,
+    pub id: Option<String>,
+    pub annotation: Option<String>,
 }
 
 pub fn parse(contents: &str) -> Result<Table, String> {
+    let mut lines: Vec<(usize, Vec<String>)> = vec![];
+
+    for (line_index, untrimmed_line) in contents.lines().enumerate() {
+        let line = untrimmed_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if lines.is_empty() && !line.contains('|') {
+            return Err(format!("line {}: not a table row", line_index + 1));
+        }
+
+        lines.push((line_index + 1, split_row(line)));
+    }
+
+    from_rows(lines)
+}
+
+/// A table from rows of cells with their line numbers - header, alignment, type and hit policy row first
+pub(crate) fn from_rows(lines: Vec<(usize, Vec<String>)>) -> Result<Table, String> {
     let mut table: Table = Table {
         rows: vec![],
         defs: Definition {
             inputs: vec![],
             outputs: vec![],
+            rule_id: None,
+            annotation: None,
+            hit_policy: None,
         },
     };
 
-    let mut current_line = 0;
     let mut definition_rows: Vec<Row> = vec![];
 
-    for untrimmed_line in contents.lines() {
-        let line = untrimmed_line.trim();
-        if line.len() == 0 {
-            continue;
-        }
-
-        let mut columns: Vec<&str> = line.split("|").collect();
-
-        // note - split by | will also create/have empty column on left first place, and most right
-        if columns.len() < 4 {
-            return Err("incorrect table column size - need at least 1 in, 1 out".to_owned());
+    for (current_line, (line_number, cells)) in lines.into_iter().enumerate() {
+        if let Some(header) = definition_rows.first() {
+            if cells.len() != header.cells.len() {
+                return Err(format!(
+                    "line {}: expected {} cells like the header row, found {}",
+                    line_number,
+                    header.cells.len(),
+                    cells.len()
+                ));
+            }
+        } else if cells.len() < 2 {
+            return Err(format!(
+                "line {}: incorrect table column size - need at least 1 in, 1 out",
+                line_number
+            ));
         }
 
-        columns.pop();
-        columns.remove(0);
-
-        let mut row = Row { cells: vec![] };
-
-        for column_content in columns {
-            row.cells.push(column_content.trim().to_string());
-        }
+        let row = Row {
+            cells,
+            id: None,
+            annotation: None,
+        };
 
         // 4 is a number of required definition rows
         if current_line >= 4 {
@@ -63,11 +132,9 @@ pub fn parse(contents: &str) -> Result<Table, String> {
         } else {
             definition_rows.push(row);
         }
-
-        current_line += 1;
     }
 
-    if table.rows.len() == 0 {
+    if table.rows.is_empty() {
         return Err("table has incorrect data row size".to_owned());
     }
 
@@ -78,6 +145,23 @@ pub fn parse(contents: &str) -> Result<Table, String> {
     let header_row: usize = 0;
     let io_row: usize = 1;
     let type_row: usize = 2;
+    let hit_policy_row: usize = 3;
+
+    table.defs.hit_policy = definition_rows[hit_policy_row]
+        .cells
+        .first()
+        .filter(|cell| !cell.is_empty() && cell.as_str() != "##")
+        .cloned();
+    if let Some(policy) = &table.defs.hit_policy {
+        if hit_policy_name(policy).is_none() {
+            return Err(format!("hit policy \"{}\" is not supported", policy));
+        }
+    }
+
+    let mut input_columns: Vec<usize> = vec![];
+    let mut output_columns: Vec<usize> = vec![];
+    let mut id_column: Option<usize> = None;
+    let mut annotation_column: Option<usize> = None;
 
     for col_index in 0..definition_rows[header_row].cells.len() {
         let io_def = &definition_rows[io_row].cells[col_index];
@@ -85,81 +169,478 @@ pub fn parse(contents: &str) -> Result<Table, String> {
         let type_variable = &definition_rows[type_row].cells[col_index];
 
         if io_def.starts_with("-") && io_def.ends_with("-") {
+            input_columns.push(col_index);
             table
                 .defs
                 .inputs
                 .push((column_variable.clone(), type_variable.clone()));
         } else if io_def.ends_with("-:") {
+            output_columns.push(col_index);
             table
                 .defs
                 .outputs
                 .push((column_variable.clone(), type_variable.clone()));
+        } else if io_def.starts_with(':') {
+            let (column, name, kind) = match type_variable.as_str() {
+                "id" => (&mut id_column, &mut table.defs.rule_id, "rule id"),
+                _ => (
+                    &mut annotation_column,
+                    &mut table.defs.annotation,
+                    "annotation",
+                ),
+            };
+            if column.is_some() {
+                return Err(format!("table has more than one {} column", kind));
+            }
+            *column = Some(col_index);
+            *name = Some(column_variable.clone());
+        } else {
+            return Err(format!(
+                "column \"{}\" has an unknown alignment \"{}\"",
+                column_variable, io_def
+            ));
         }
     }
 
+    if table.defs.inputs.is_empty() || table.defs.outputs.is_empty() {
+        return Err("table needs at least 1 input and 1 output column".to_string());
+    }
+    table.defs.check_result_headers()?;
+
+    // cells are kept as inputs followed by outputs, whatever the column order
+    for row in table.rows.iter_mut() {
+        let mut cells = std::mem::take(&mut row.cells);
+        let optional = |column: Option<usize>, cells: &mut Vec<String>| {
+            column
+                .map(|c| std::mem::take(&mut cells[c]))
+                .filter(|cell| !cell.is_empty())
+        };
+        row.id = optional(id_column, &mut cells);
+        row.annotation = optional(annotation_column, &mut cells);
+        row.cells = input_columns
+            .iter()
+            .chain(output_columns.iter())
+            .map(|&c| std::mem::take(&mut cells[c]))
+            .collect();
+    }
+
     Ok(table)
 }
 
+/*
+    Splits a table row into trimmed cells, following GFM:
+    - leading and trailing pipes are optional
+    - `\|` is a pipe inside a cell
+    - pipes inside `code spans` do not split cells
+*/
+pub(crate) fn split_row(line: &str) -> Vec<String> {
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let chars: Vec<char> = line.chars().collect();
+
+    let mut cells: Vec<String> = vec![];
+    let mut cell = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'|') => {
+                cell.push('|');
+                i += 2;
+            }
+            '`' => {
+                let ticks = chars[i..].iter().take_while(|c| **c == '`').count();
+                match find_code_span_end(&chars, i + ticks, ticks) {
+                    Some(end) => {
+                        cell.extend(&chars[i..end]);
+                        i = end;
+                    }
+                    None => {
+                        cell.extend(&chars[i..i + ticks]);
+                        i += ticks;
+                    }
+                }
+            }
+            '|' => {
+                cells.push(cell.trim().to_string());
+                cell.clear();
+                i += 1;
+            }
+            c => {
+                cell.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    // no trailing pipe - the last cell is still open
+    if !cell.trim().is_empty() || !line.ends_with('|') || line.ends_with("\\|") {
+        cells.push(cell.trim().to_string());
+    }
+
+    cells.into_iter().map(unwrap_code_span).collect()
+}
+
+/// Index after the backtick run of exactly `ticks` length that closes a code span
+fn find_code_span_end(chars: &[char], from: usize, ticks: usize) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if chars[i] != '`' {
+            i += 1;
+            continue;
+        }
+
+        let run = chars[i..].iter().take_while(|c| **c == '`').count();
+        if run == ticks {
+            return Some(i + run);
+        }
+        i += run;
+    }
+
+    None
+}
+
+/// A cell that is one code span, e.g. `"a|b"`, holds the expression inside it
+fn unwrap_code_span(cell: String) -> String {
+    let ticks = cell.chars().take_while(|c| *c == '`').count();
+    if ticks == 0 || cell.len() <= ticks * 2 {
+        return cell;
+    }
+
+    let delimiter = &cell[..ticks];
+    let inner = &cell[ticks..cell.len() - ticks];
+    if cell.ends_with(delimiter)
+        && !inner.starts_with('`')
+        && !inner.ends_with('`')
+        && !inner.contains(delimiter)
+    {
+        return inner.trim().to_string();
+    }
+
+    cell
+}
+
 pub fn run_table(
     table: &Table,
     context: &serde_json::Value,
+) -> Result<Vec<HashMap<String, Operand>>, String> {
+    evaluate_rules(table, context, None)
+}
+
+pub(crate) fn evaluate_rules(
+    table: &Table,
+    context: &serde_json::Value,
+    mut trace: Option<&mut TableTrace>,
 ) -> Result<Vec<HashMap<String, Operand>>, String> {
     let mut outputs: Vec<HashMap<String, Operand>> = vec![];
+    let mut matched_rows: Vec<usize> = vec![];
     let mut row_is_true;
 
-    for row_index in 0..table.rows.len() {
+    // input expressions are computed once, then tested by every rule
+    let inputs: Vec<Operand> = table
+        .defs
+        .inputs
+        .iter()
+        .map(|(header, var_type)| column_input(header, var_type, context))
+        .collect::<Result<_, String>>()?;
+
+    for (row_index, row) in table.rows.iter().enumerate() {
         row_is_true = true;
+        let mut cell_traces: Vec<CellTrace> = vec![];
 
-        for col_index in 0..table.defs.inputs.len() {
-            let (var_name, _var_type) = &table.defs.inputs[col_index];
-            let input_operand = var_to_operand(var_name, &context);
-            let column_value = &table.rows[row_index].cells[col_index];
-            let mut parser = Tokenizer::new(&column_value);
+        for (col_index, (var_name, var_type)) in table.defs.inputs.iter().enumerate() {
+            let input_operand = inputs[col_index].clone();
+            let column_value = &row.cells[col_index];
+
+            // the row already failed, the remaining cells are not evaluated
+            if !row_is_true {
+                if trace.is_some() {
+                    cell_traces.push(CellTrace {
+                        column: var_name.clone(),
+                        expression: column_value.clone(),
+                        input: input_operand.to_value(),
+                        outcome: CellOutcome::Skipped,
+                    });
+                    continue;
+                }
+                break;
+            }
+
+            if is_wildcard(column_value) {
+                if trace.is_some() {
+                    cell_traces.push(CellTrace {
+                        column: var_name.clone(),
+                        expression: column_value.clone(),
+                        input: input_operand.to_value(),
+                        outcome: CellOutcome::Matched,
+                    });
+                }
+                continue;
+            }
+
+            let number_mode = column_number_mode(var_type);
+            let mut parser = Tokenizer::with_number_mode(column_value, number_mode);
 
             parser.parse()?;
 
-            let start_with_operand = parser.starts_with_operand();
-            if !start_with_operand {
-                parser.insert_start(input_operand);
-                parser.insert_start(Operand::OperatorToken(Operator::E));
-            } else {
-                parser.insert_start(input_operand);
+            if trace.is_some() {
+                cell_traces.push(CellTrace {
+                    column: var_name.clone(),
+                    expression: column_value.clone(),
+                    input: input_operand.to_value(),
+                    outcome: CellOutcome::NotMatched,
+                });
+            }
+
+            // "?" cells test the input themselves, e.g. starts with(?, "A")
+            if !parser.replace_placeholder(&input_operand) {
+                let start_with_operand = parser.starts_with_operand();
+                if !start_with_operand {
+                    parser.insert_start(input_operand);
+                    parser.insert_start(Operand::OperatorToken(Operator::E));
+                } else {
+                    parser.insert_start(input_operand);
+                }
             }
 
             let expression = parser.to_postfix()?;
-            let expr_result = interpret(&expression);
-            if let Some(
-                // Operand::Boolean(true)
-                Operand::Primitive(Value::Bool(true)),
-            ) = expr_result.get(0)
-            {
+            let expr_result = interpret_with_mode(&expression, context, number_mode)?;
+            if let Some(Operand::Primitive(Value::Bool(true))) = expr_result.first() {
                 row_is_true = true;
+                if let Some(cell) = cell_traces.last_mut() {
+                    cell.outcome = CellOutcome::Matched;
+                }
             } else {
                 row_is_true = false;
-                break;
             }
         }
 
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.rules.push(RuleTrace {
+                row: row_index,
+                id: row.id.clone(),
+                annotation: row.annotation.clone(),
+                matched: row_is_true,
+                cells: cell_traces,
+            });
+        }
+
         if row_is_true {
             let mut output_result: HashMap<String, Operand> = HashMap::new();
 
             let offset = table.defs.inputs.len();
-            for col_index in 0..table.defs.outputs.len() {
-                let column_output_value = &table.rows[row_index].cells[col_index + offset];
-                let (out_key, _operand_type) = &table.defs.outputs[col_index];
+            for (col_index, (out_key, operand_type)) in table.defs.outputs.iter().enumerate() {
+                let column_output_value = &row.cells[col_index + offset];
                 output_result.insert(
                     out_key.to_owned(),
-                    Operand::Primitive(Value::String(column_output_value.to_owned())),
+                    evaluate_output(
+                        column_output_value,
+                        context,
+                        column_number_mode(operand_type),
+                    )
+                    .map_err(|e| format!("output \"{}\": {}", out_key, e))?,
                 );
             }
 
+            // rule id and annotation tie the result back to the documented rule
+            let documentation = [
+                (&table.defs.rule_id, &row.id),
+                (&table.defs.annotation, &row.annotation),
+            ];
+            for (header, cell) in documentation {
+                if let (Some(header), Some(cell)) = (header, cell) {
+                    output_result.insert(
+                        header.clone(),
+                        Operand::Primitive(Value::String(cell.clone())),
+                    );
+                }
+            }
+
             outputs.push(output_result);
+            matched_rows.push(row_index);
         }
     }
 
+    apply_hit_policy(&table.defs, outputs, &matched_rows)
+}
+
+/// markdown abbreviation, DMN name - the hit policies evaluation applies
+pub(crate) const HIT_POLICIES: [(&str, &str); 5] = [
+    ("U", "UNIQUE"),
+    ("F", "FIRST"),
+    ("A", "ANY"),
+    ("C", "COLLECT"),
+    ("R", "RULE ORDER"),
+];
+
+/// The DMN name of a hit policy, `F` and `first` are both "FIRST"
+pub(crate) fn hit_policy_name(policy: &str) -> Option<&'static str> {
+    HIT_POLICIES
+        .iter()
+        .find(|(abbreviation, name)| *abbreviation == policy || name.eq_ignore_ascii_case(policy))
+        .map(|(_, name)| *name)
+}
+
+/// Results of the matching rules as the hit policy says, all of them when it is not set
+fn apply_hit_policy(
+    defs: &Definition,
+    mut outputs: Vec<HashMap<String, Operand>>,
+    matched_rows: &[usize],
+) -> Result<Vec<HashMap<String, Operand>>, String> {
+    let Some(policy) = &defs.hit_policy else {
+        return Ok(outputs);
+    };
+
+    // rules are counted from 1, like the rows a reader sees
+    match hit_policy_name(policy) {
+        Some("COLLECT" | "RULE ORDER") => {}
+        Some("FIRST") => outputs.truncate(1),
+        Some("UNIQUE") => {
+            if let [first, second, ..] = matched_rows {
+                return Err(format!(
+                    "rules {} and {} both match, the hit policy is UNIQUE",
+                    first + 1,
+                    second + 1
+                ));
+            }
+        }
+        Some("ANY") => {
+            // outputs agree by value, 8 and 8.0 are the same output
+            let differs = |result: &HashMap<String, Operand>| {
+                defs.outputs.iter().any(|(output, _)| {
+                    match (result.get(output), outputs[0].get(output)) {
+                        (Some(o1), Some(o2)) => !o1.equals(o2),
+                        (o1, o2) => o1.is_some() || o2.is_some(),
+                    }
+                })
+            };
+            if let Some(index) = outputs.iter().position(differs) {
+                return Err(format!(
+                    "rules {} and {} match with different outputs, the hit policy is ANY",
+                    matched_rows[0] + 1,
+                    matched_rows[index] + 1
+                ));
+            }
+            outputs.truncate(1);
+        }
+        _ => return Err(format!("hit policy \"{}\" is not supported", policy)),
+    }
     Ok(outputs)
 }
 
+/// `-` or an empty input cell matches any input
+pub(crate) fn is_wildcard(cell: &str) -> bool {
+    cell.is_empty() || cell == "-"
+}
+
+/// `decimal` columns are evaluated with exact decimal arithmetic
+fn column_number_mode(column_type: &str) -> NumberMode {
+    match column_type {
+        "decimal" => NumberMode::Decimal,
+        _ => NumberMode::Float,
+    }
+}
+
+/// Value of an input column, read as the declared decimal or temporal type.
+/// The header is a context path (`season`, `order-id`) or an expression (`guestCount / tables`)
+fn column_input(
+    header: &str,
+    var_type: &str,
+    context: &serde_json::Value,
+) -> Result<Operand, String> {
+    let operand = match context_path(header, context) {
+        Some(path) => var_to_operand(&path, context),
+        None => evaluate_output(header, context, column_number_mode(var_type))
+            .map_err(|e| format!("input \"{}\": {}", header, e))?,
+    };
+
+    if var_type == "decimal" {
+        let decimal = match &operand {
+            Operand::Decimal(d) => Some(d.clone()),
+            Operand::Primitive(Value::Number(n)) => Decimal::from_number(n),
+            Operand::Primitive(Value::String(s)) => Decimal::parse(s).ok(),
+            _ => None,
+        };
+        return Ok(match decimal {
+            Some(d) => Operand::Decimal(d),
+            None => Operand::Primitive(Value::Null),
+        });
+    }
+
+    let Operand::Primitive(Value::String(s)) = &operand else {
+        return Ok(operand);
+    };
+
+    let parsed = match var_type {
+        "date" => Temporal::parse_date(s),
+        "time" => Temporal::parse_time(s),
+        "date and time" => Temporal::parse_date_time(s),
+        "duration" => Temporal::parse_duration(s),
+        _ => return Ok(operand),
+    };
+
+    Ok(match parsed {
+        Ok(t) => Operand::Temporal(t),
+        Err(_) => Operand::Primitive(Value::Null),
+    })
+}
+
+/// The context path of a header that is just a variable, None for an expression
+pub(crate) fn input_path(header: &str) -> Option<String> {
+    let mut parser = Tokenizer::new(header);
+    parser.parse().ok()?;
+
+    match parser.operands() {
+        [Operand::Variable(path)] => Some(path.clone()),
+        _ => None,
+    }
+}
+
+/// The context path of a header: the header itself when the context has it, as for
+/// `order-id` or `guest count`, else its `input_path`
+pub(crate) fn context_path(header: &str, context: &serde_json::Value) -> Option<String> {
+    if !get_context_var(header, context).is_null() {
+        return Some(header.to_string());
+    }
+    input_path(header)
+}
+
+/// Context variables an input header reads, e.g. `guestCount` and `tables` for `guestCount / tables`
+pub(crate) fn input_variables(header: &str) -> Vec<String> {
+    let mut parser = Tokenizer::new(header);
+    if parser.parse().is_err() {
+        return vec![header.to_string()];
+    }
+
+    parser
+        .operands()
+        .iter()
+        .filter_map(|o| match o {
+            Operand::Variable(v) => Some(v.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Output cells are expressions too, e.g. "Roastbeef" or upper case(season)
+fn evaluate_output(
+    cell: &str,
+    context: &serde_json::Value,
+    number_mode: NumberMode,
+) -> Result<Operand, String> {
+    let mut parser = Tokenizer::with_number_mode(cell, number_mode);
+    parser.parse()?;
+
+    let expression = parser.to_postfix()?;
+    let mut expr_result = interpret_with_mode(&expression, context, number_mode)?;
+    if expr_result.len() > 1 {
+        return Err(format!("\"{}\" is not a single expression", cell));
+    }
+
+    Ok(expr_result.pop().unwrap_or(Operand::Primitive(Value::Null)))
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::needless_borrow)]
 mod tests {
     use super::*;
     use std::fs;
@@ -203,14 +684,372 @@ mod tests {
         assert_eq!(firs_res.contains_key("desiredDish"), true);
         assert_eq!(
             firs_res.get("desiredDish"),
+            Some(&Operand::Primitive(Value::String("Spaceribs".to_owned())))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_functions() -> Result<(), String> {
+        let contents = r#"
+        | season                   | guestCount | dish                             |
+        |--------------------------|------------|---------------------------------:|
+        | string                   | number     |                           string |
+        | ##                       | ##         |                               ## |
+        | starts with(?, "Wi")     | >= 8       |                      "Roastbeef" |
+        | contains(lower case(?), "fa") | abs(?) < 10 | upper case(season) + "-ribs" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "season": "Fall", "guestCount": 8 });
+        let res = run_table(&table, &context)?;
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("dish"),
+            Some(&Operand::Primitive(Value::String("FALL-ribs".to_owned())))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_dates() -> Result<(), String> {
+        let contents = r#"
+        | orderDate                 | deliveryWindow         | sla                            |
+        |---------------------------|------------------------|-------------------------------:|
+        | date                      | duration               |                         string |
+        | ##                        | ##                     |                             ## |
+        | < date("2024-01-01")      | <= duration("P2D")     |                       "legacy" |
+        | >= date("2024-01-01")     | <= duration("P2D")     | date(orderDate) + duration("P1M") |
+        | >= date("2024-01-01")     | > duration("P2D")      |                       "manual" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "orderDate": "2024-01-31", "deliveryWindow": "PT36H" });
+        let res = run_table(&table, &context)?;
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("sla"),
+            Some(&Operand::Temporal(Temporal::parse_date("2024-02-29")?))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_decimals() -> Result<(), String> {
+        let contents = r#"
+        | total            | fee                   | payable                  |
+        |------------------|-----------------------|-------------------------:|
+        | decimal          | decimal               |                  decimal |
+        | ##               | ##                    |                       ## |
+        | = 0.3            | <= 0.1                |              fee + 0.3 |
+        | != 0.3           | >= 0                  |                        0 |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "total": "0.30", "fee": 0.1 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("payable"),
+            Some(&Operand::Decimal(Decimal::parse("0.4")?))
+        );
+
+        // 0.1 + 0.2 is exactly 0.3 in decimal columns
+        let contents = contents.replace("= 0.3            | <= 0.1", "= 0.1 + 0.2      | <= 0.1");
+        let table = parse(&contents)?;
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn splits_rows_like_gfm() {
+        assert_eq!(split_row("| a | b |"), vec!["a", "b"]);
+        assert_eq!(split_row("a | b"), vec!["a", "b"]);
+        assert_eq!(split_row("| a | |"), vec!["a", ""]);
+        assert_eq!(split_row(r#"| "x \| y" | b |"#), vec![r#""x | y""#, "b"]);
+        assert_eq!(split_row(r#"a | "b \|"#), vec!["a", r#""b |"#]);
+        assert_eq!(
+            split_row(r#"| `"x | y"` | `` a`|`b `` |"#),
+            vec![r#""x | y""#, "a`|`b"]
+        );
+        assert_eq!(split_row("| `a | b |"), vec!["`a", "b"]);
+    }
+
+    #[test]
+    fn execute_md_table_with_pipes() -> Result<(), String> {
+        let contents = r#"
+        product                  | price  | label
+        -------------------------|--------|------------------:
+        string                   | number | string
+        ##                       | ##     | ##
+        `"Salt | Pepper"`        | < 10   | "cheap \| mixed"
+        "Salt \| Pepper"         | >= 10  | "premium"
+        "#;
+        let table = parse(contents)?;
+        assert_eq!(table.defs.inputs.len(), 2);
+        assert_eq!(table.defs.outputs.len(), 1);
+
+        let context = serde_json::json!({ "product": "Salt | Pepper", "price": 12 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("label"),
+            Some(&Operand::Primitive(Value::String("premium".to_owned())))
+        );
+
+        let context = serde_json::json!({ "product": "Salt | Pepper", "price": 2 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(
+            res[0].get("label"),
             Some(&Operand::Primitive(Value::String(
-                "\"Spaceribs\"".to_owned()
+                "cheap | mixed".to_owned()
             )))
         );
 
         Ok(())
     }
 
+    #[test]
+    fn md_table_reports_wrong_cell_count() {
+        let contents = r#"
+        | season   | guestCount | desiredDish |
+        |----------|------------|------------:|
+        | string   | number     |      string |
+        | ##       | ##         |          ## |
+        | "a|b"    | 8          | "Spaceribs" |
+        "#;
+
+        let err = parse(contents).unwrap_err();
+        assert!(err.contains("line 6"), "{}", err);
+        assert!(err.contains("expected 3 cells"), "{}", err);
+    }
+
+    #[test]
+    fn execute_md_table_with_wildcards() -> Result<(), String> {
+        let contents = r#"
+        | season   | guestCount | desiredDish |
+        |----------|------------|------------:|
+        | string   | number     |      string |
+        | ##       | ##         |          ## |
+        | "Fall"   | -          | "Spaceribs" |
+        | -        | > 8        |      "Stew" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "season": "Fall", "guestCount": 10 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 2);
+
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 2 });
+        assert!(run_table(&table, &context)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_booleans_and_lists() -> Result<(), String> {
+        let contents = r#"
+        | vegetarian | allergies                      | dish       |
+        |------------|--------------------------------|-----------:|
+        | boolean    | list                           |     string |
+        | ##         | ##                             |         ## |
+        | true       | list contains(?, "nuts")       |    "Salad" |
+        | true       | -                              |    "Pesto" |
+        | false      | -                              |     "Ribs" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "vegetarian": true, "allergies": ["nuts"] });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res[0].get("dish"),
+            Some(&Operand::Primitive(Value::String("Salad".to_owned())))
+        );
+
+        let context = serde_json::json!({ "vegetarian": false, "allergies": [] });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("dish"),
+            Some(&Operand::Primitive(Value::String("Ribs".to_owned())))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_input_expressions() -> Result<(), String> {
+        let contents = r#"
+        | guestCount / tables | upper case(season) | layout    |
+        |---------------------|--------------------|----------:|
+        | number              | string             |    string |
+        | ##                  | ##                 |        ## |
+        | > 6                 | "FALL"             | "crowded" |
+        | <= 6                | -                  |    "cosy" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "season": "Fall", "guestCount": 14, "tables": 2 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("layout"),
+            Some(&Operand::Primitive(Value::String("crowded".to_owned())))
+        );
+
+        assert_eq!(
+            input_path("customer.name"),
+            Some("customer.name".to_string())
+        );
+        assert_eq!(input_path("guestCount / tables"), None);
+        assert_eq!(
+            input_variables("guestCount / tables"),
+            vec!["guestCount", "tables"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_headers_that_are_not_plain_paths() -> Result<(), String> {
+        let contents = r#"
+        | order-id | guest count | table  |
+        |----------|-------------|-------:|
+        | number   | number      | string |
+        | ##       | ##          |     ## |
+        | 7        | > 8         |  "big" |
+        | 7        | <= 8        | "cosy" |
+        "#;
+        let table = parse(contents)?;
+
+        let context = serde_json::json!({ "order-id": 7, "guest count": 12 });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(
+            res[0].get("table"),
+            Some(&Operand::Primitive(Value::String("big".to_owned())))
+        );
+
+        // missing, the header is evaluated and fails as an input
+        let err = run_table(&table, &serde_json::json!({ "order-id": 7 })).unwrap_err();
+        assert!(err.starts_with("input \"guest count\": "), "{}", err);
+        assert!(!err.contains("output"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn execute_md_table_with_rule_ids_and_annotations() -> Result<(), String> {
+        let contents = r#"
+        | rule  | season   | notes                    | desiredDish |
+        |:------|----------|:-------------------------|------------:|
+        | id    | string   | string                   |      string |
+        | ##    | ##       | ##                       |          ## |
+        | R-1   | "Fall"   | ribs sell best in autumn | "Spaceribs" |
+        | R-2   | "Winter" |                          | "Roastbeef" |
+        "#;
+        let table = parse(contents)?;
+        assert_eq!(table.defs.rule_id, Some("rule".to_string()));
+        assert_eq!(table.defs.annotation, Some("notes".to_string()));
+        assert_eq!(table.rows[0].cells, vec!["\"Fall\"", "\"Spaceribs\""]);
+        assert_eq!(table.rows[1].annotation, None);
+
+        let context = serde_json::json!({ "season": "Fall" });
+        let res = run_table(&table, &context)?;
+        assert_eq!(
+            res[0].get("rule"),
+            Some(&Operand::Primitive(Value::String("R-1".to_owned())))
+        );
+        assert_eq!(
+            res[0].get("notes"),
+            Some(&Operand::Primitive(Value::String(
+                "ribs sell best in autumn".to_owned()
+            )))
+        );
+
+        let context = serde_json::json!({ "season": "Winter" });
+        let res = run_table(&table, &context)?;
+        assert_eq!(res[0].get("notes"), None);
+
+        let twice = contents.replace("| id    |", "| string |");
+        assert_eq!(
+            parse(&twice).unwrap_err(),
+            "table has more than one annotation column"
+        );
+
+        let clash = contents.replace("| notes   ", "| desiredDish");
+        assert_eq!(
+            parse(&clash).unwrap_err(),
+            "annotation column \"desiredDish\" has the name of an output"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn applies_hit_policies() -> Result<(), String> {
+        let table = |hit_policy: &str| {
+            parse(&format!(
+                r#"
+        | season   | guestCount | dish   |
+        |----------|------------|-------:|
+        | string   | number     | string |
+        | {:<8} | ##         |     ## |
+        | "Fall"   | <= 8       | "Ribs" |
+        | "Fall"   | -          | "Stew" |
+        | -        | <= 8       | "Ribs" |
+        "#,
+                hit_policy
+            ))
+        };
+        let dishes = |hit_policy: &str, guests: u32| -> Result<Vec<String>, String> {
+            let context = serde_json::json!({ "season": "Fall", "guestCount": guests });
+            Ok(run_table(&table(hit_policy)?, &context)?
+                .iter()
+                .map(|result| result["dish"].to_value().to_string())
+                .collect())
+        };
+
+        assert_eq!(dishes("##", 4)?, vec!["\"Ribs\"", "\"Stew\"", "\"Ribs\""]);
+        assert_eq!(dishes("C", 4)?.len(), 3);
+        assert_eq!(dishes("R", 4)?.len(), 3);
+        assert_eq!(dishes("FIRST", 4)?, vec!["\"Ribs\""]);
+        assert_eq!(dishes("U", 12)?, vec!["\"Stew\""]);
+        assert_eq!(
+            dishes("U", 4).unwrap_err(),
+            "rules 1 and 2 both match, the hit policy is UNIQUE"
+        );
+        assert_eq!(
+            dishes("A", 4).unwrap_err(),
+            "rules 1 and 2 match with different outputs, the hit policy is ANY"
+        );
+        let context = serde_json::json!({ "season": "Winter", "guestCount": 4 });
+        assert_eq!(run_table(&table("A")?, &context)?.len(), 1);
+
+        let prices = parse(
+            r#"
+        | season   | price  |
+        |----------|-------:|
+        | string   | number |
+        | A        |     ## |
+        | "Fall"   | 8      |
+        | -        | 8.0    |
+        "#,
+        )?;
+        let context = serde_json::json!({ "season": "Fall" });
+        assert_eq!(run_table(&prices, &context)?.len(), 1);
+
+        assert_eq!(table("P").unwrap_err(), "hit policy \"P\" is not supported");
+        Ok(())
+    }
+
     #[test]
     fn md_table_expect_failure_insufficient_wrows() -> Result<(), String> {
         let contents = r#"
@@ -0,0 +1,355 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::document::{is_decision_table, parse_document, table_blocks, Decision};
+use super::{run_table, split_row};
+use crate::context::set_context_var;
+use crate::expression_parser::operand::Operand;
+
+/*
+    Test cases kept next to the decision they test. A table under a `Tests`
+    heading tests the decision table above it, under `<Name> tests` the named
+    decision - a name the document has no decision for is an error. Input
+    columns (---) hold the context, output columns (---:) the expected outputs
+    and an optional :--- column names the case. Cells are JSON, an empty cell
+    is left out of the context or not checked, and an expected `null` means no
+    rule matches.
+*/
+
+#[derive(Debug, PartialEq)]
+pub struct TestCase {
+    pub decision: String,
+    /// the name cell, "line N" without one
+    pub name: String,
+    pub context: Value,
+    pub expected: Vec<(String, Value)>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TestResult {
+    pub decision: String,
+    pub name: String,
+    pub mismatches: Vec<Mismatch>,
+    /// the decision could not run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Mismatch {
+    pub output: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty() && self.error.is_none()
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if self.passed() { "ok" } else { "FAILED" };
+        write!(f, "{} {}: {}", status, self.decision, self.name)?;
+        if let Some(error) = &self.error {
+            write!(f, "\n    error: {}", error)?;
+        }
+        for mismatch in self.mismatches.iter() {
+            write!(
+                f,
+                "\n    {}: expected {}, found {}",
+                mismatch.output, mismatch.expected, mismatch.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Every test case of a markdown document
+pub fn parse_tests(contents: &str) -> Result<Vec<TestCase>, String> {
+    let lines: Vec<&str> = contents.lines().map(str::trim).collect();
+    let blocks = table_blocks(&lines);
+
+    // named like `parse_document` names them
+    let mut names: Vec<String> = vec![];
+    for block in blocks.iter() {
+        if is_decision_table(&lines[block.start..block.end]) {
+            let name = block.heading.clone();
+            names.push(name.unwrap_or_else(|| format!("table_{}", names.len() + 1)));
+        }
+    }
+
+    let mut cases: Vec<TestCase> = vec![];
+    let mut decisions_above = 0;
+    for block in blocks {
+        if is_decision_table(&lines[block.start..block.end]) {
+            decisions_above += 1;
+            continue;
+        }
+        let Some(heading) = block.heading.as_deref() else {
+            continue;
+        };
+        let decision = tested_decision(heading, &names[..decisions_above], &names)
+            .map_err(|e| format!("line {}: {}", block.start + 1, e))?;
+        let Some(decision) = decision else {
+            continue;
+        };
+
+        let rows: Vec<(usize, Vec<String>)> = (block.start..block.end)
+            .map(|i| (i + 1, split_row(lines[i])))
+            .collect();
+        cases.extend(parse_cases(&decision, &rows)?);
+    }
+
+    Ok(cases)
+}
+
+/// The decision tested under `heading`, None for a heading of any other table
+fn tested_decision(
+    heading: &str,
+    above: &[String],
+    names: &[String],
+) -> Result<Option<String>, String> {
+    if heading.eq_ignore_ascii_case("tests") {
+        return match above.last() {
+            Some(last) => Ok(Some(last.clone())),
+            None => Err("tests without a decision table above".to_string()),
+        };
+    }
+
+    let suffix = " tests";
+    let split = heading.len().saturating_sub(suffix.len());
+    if !heading
+        .get(split..)
+        .is_some_and(|end| end.eq_ignore_ascii_case(suffix))
+    {
+        return Ok(None);
+    }
+    let named = heading[..split].trim();
+    if !names.iter().any(|name| name == named) {
+        return Err(format!("tests for unknown decision \"{}\"", named));
+    }
+    Ok(Some(named.to_string()))
+}
+
+fn parse_cases(decision: &str, rows: &[(usize, Vec<String>)]) -> Result<Vec<TestCase>, String> {
+    let header = &rows[0].1;
+    let alignments = &rows[1].1;
+    let mut cases: Vec<TestCase> = vec![];
+
+    for (line_number, cells) in rows[2..].iter() {
+        if cells.len() != header.len() {
+            return Err(format!(
+                "line {}: expected {} cells like the header row, found {}",
+                line_number,
+                header.len(),
+                cells.len()
+            ));
+        }
+
+        let mut case = TestCase {
+            decision: decision.to_string(),
+            name: format!("line {}", line_number),
+            context: Value::Object(serde_json::Map::new()),
+            expected: vec![],
+        };
+
+        for ((column, alignment), cell) in header.iter().zip(alignments).zip(cells) {
+            if cell.is_empty() {
+                continue;
+            }
+            if alignment.starts_with(':') {
+                case.name = cell.clone();
+                continue;
+            }
+
+            let value: Value = serde_json::from_str(cell).map_err(|_| {
+                format!(
+                    "line {}, column \"{}\": {} is not a JSON value",
+                    line_number, column, cell
+                )
+            })?;
+            if alignment.ends_with(':') {
+                case.expected.push((column.clone(), value));
+            } else {
+                set_context_var(column, &mut case.context, value);
+            }
+        }
+
+        cases.push(case);
+    }
+
+    Ok(cases)
+}
+
+pub fn run_tests(decisions: &[Decision], cases: &[TestCase]) -> Vec<TestResult> {
+    cases
+        .iter()
+        .map(|case| {
+            let mut result = TestResult {
+                decision: case.decision.clone(),
+                name: case.name.clone(),
+                mismatches: vec![],
+                error: None,
+            };
+            match run_case(decisions, case) {
+                Ok(mismatches) => result.mismatches = mismatches,
+                Err(e) => result.error = Some(e),
+            }
+            result
+        })
+        .collect()
+}
+
+/// Parses the decisions and test cases of a document and runs them
+pub fn test_document(contents: &str) -> Result<Vec<TestResult>, String> {
+    let decisions = parse_document(contents)?;
+    let cases = parse_tests(contents)?;
+    Ok(run_tests(&decisions, &cases))
+}
+
+fn run_case(decisions: &[Decision], case: &TestCase) -> Result<Vec<Mismatch>, String> {
+    let decision = decisions
+        .iter()
+        .find(|d| d.name == case.decision)
+        .ok_or_else(|| format!("no decision named \"{}\"", case.decision))?;
+    let table = &decision.table;
+    let results = run_table(table, &case.context)?;
+
+    let mut mismatches: Vec<Mismatch> = vec![];
+    for (output, expected) in case.expected.iter() {
+        let known = table.defs.outputs.iter().any(|(name, _)| name == output)
+            || table.defs.rule_id.as_ref() == Some(output)
+            || table.defs.annotation.as_ref() == Some(output);
+        if !known {
+            return Err(format!("the decision has no output \"{}\"", output));
+        }
+
+        let actual = actual_value(&results, output);
+        if !Operand::Primitive(expected.clone()).equals(&Operand::Primitive(actual.clone())) {
+            mismatches.push(Mismatch {
+                output: output.clone(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// The output of the matching rule, null without one and a list for several
+fn actual_value(results: &[HashMap<String, Operand>], output: &str) -> Value {
+    let mut values: Vec<Value> = results
+        .iter()
+        .map(|r| r.get(output).map_or(Value::Null, Operand::to_value))
+        .collect();
+    match values.len() {
+        0 => Value::Null,
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn runs_tests_in_sample_document() -> Result<(), String> {
+        let contents = fs::read_to_string("./samples/decisions.md")
+            .expect("Something went wrong reading the TEST file");
+
+        let cases = parse_tests(&contents)?;
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].decision, "Dish");
+        assert_eq!(cases[0].name, "small fall");
+        assert_eq!(
+            cases[0].context,
+            serde_json::json!({ "season": "Fall", "guestCount": 4 })
+        );
+
+        let results = test_document(&contents)?;
+        assert!(results.iter().all(|r| r.passed()), "{:?}", results);
+        Ok(())
+    }
+
+    #[test]
+    fn reports_mismatches_and_errors() -> Result<(), String> {
+        let contents = r#"
+## Dish
+
+| season   | guestCount | desiredDish |
+|----------|------------|------------:|
+| string   | number     |      string |
+| ##       | ##         |          ## |
+| "Fall"   | <= 8       | "Spaceribs" |
+| "Fall"   | <= 8       | "Stew"      |
+
+## Dish tests
+
+| season | order.guests | guestCount | desiredDish             | wine |
+|--------|--------------|------------|------------------------:|-----:|
+| "Fall" | 2            | 4          | "Spaceribs"             |      |
+| "Fall" |              | 4          | ["Spaceribs", "Stew"]   |      |
+| "Fall" |              | 4          |                         | true |
+"#;
+
+        let results = test_document(contents)?;
+        let report: Vec<String> = results.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            report,
+            vec![
+                "FAILED Dish: line 15\n    desiredDish: expected \"Spaceribs\", found [\"Spaceribs\",\"Stew\"]",
+                "ok Dish: line 16",
+                "FAILED Dish: line 17\n    error: the decision has no output \"wine\"",
+            ]
+        );
+
+        let json = serde_json::to_value(&results[0]).map_err(|e| e.to_string())?;
+        assert_eq!(
+            json["mismatches"][0]["output"],
+            serde_json::json!("desiredDish")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_cells_that_are_not_json() {
+        let contents = "## Dish\n| a | b |\n|---|--:|\n| string | string |\n| ## | ## |\n| 1 | 2 |\n\n### Tests\n| a | b |\n|---|--:|\n| Fall | 2 |\n";
+        let err = parse_tests(contents).unwrap_err();
+        assert_eq!(err, "line 11, column \"a\": Fall is not a JSON value");
+    }
+
+    #[test]
+    fn matches_tests_to_decisions_by_heading() -> Result<(), String> {
+        let dish = "## Dish\n| season | dish |\n|---|--:|\n| string | string |\n| ## | ## |\n| \"Fall\" | \"Stew\" |\n\n";
+        let tests = "| season | dish |\n|---|--:|\n| \"Fall\" | \"Stew\" |\n";
+
+        // prose tables and headings that only end in "tests" are not tests
+        let contents = format!(
+            "{}## Prices\n{}\n## Contests\n{}\n## Dish tests\n{}",
+            dish, tests, tests, tests
+        );
+        let cases = parse_tests(&contents)?;
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].decision, "Dish");
+
+        let contents = format!("{}## Lunch tests\n{}", dish, tests);
+        assert_eq!(
+            parse_tests(&contents).unwrap_err(),
+            "line 9: tests for unknown decision \"Lunch\""
+        );
+
+        let contents = format!("### Tests\n{}\n{}", tests, dish);
+        assert_eq!(
+            parse_tests(&contents).unwrap_err(),
+            "line 2: tests without a decision table above"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::{evaluate_rules, Table};
+use crate::expression_parser::operand::Operand;
+
+/*
+    Why a table produced its result - for every rule, every input cell with
+    the input value it was tested against and how the test ended
+*/
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct TableTrace {
+    pub rules: Vec<RuleTrace>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RuleTrace {
+    /// index into `Table::rows`
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+    pub matched: bool,
+    pub cells: Vec<CellTrace>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CellTrace {
+    pub column: String,
+    pub expression: String,
+    pub input: Value,
+    pub outcome: CellOutcome,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CellOutcome {
+    Matched,
+    NotMatched,
+    /// an earlier cell of the rule did not match
+    Skipped,
+}
+
+/// `run_table` that also returns the trace of every rule
+pub fn run_table_traced(
+    table: &Table,
+    context: &Value,
+) -> Result<(Vec<HashMap<String, Operand>>, TableTrace), String> {
+    let mut trace = TableTrace::default();
+    let outputs = evaluate_rules(table, context, Some(&mut trace))?;
+
+    Ok((outputs, trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::parse;
+
+    #[test]
+    fn traces_rules_and_cells() -> Result<(), String> {
+        let contents = r#"
+        | income | country | decision   |
+        |--------|---------|-----------:|
+        | number | string  |     string |
+        | ##     | ##      |         ## |
+        | < 1000 | "NL"    |   "reject" |
+        | >= 1000 | "NL"   |   "accept" |
+        "#;
+        let table = parse(contents)?;
+        let context = serde_json::json!({ "income": 1500, "country": "NL" });
+
+        let (outputs, trace) = run_table_traced(&table, &context)?;
+        assert_eq!(outputs, crate::logic_table::run_table(&table, &context)?);
+        assert_eq!(trace.rules.len(), 2);
+
+        let rejected = &trace.rules[0];
+        assert!(!rejected.matched);
+        assert_eq!(rejected.cells[0].expression, "< 1000");
+        assert_eq!(rejected.cells[0].input, serde_json::json!(1500));
+        assert_eq!(rejected.cells[0].outcome, CellOutcome::NotMatched);
+        assert_eq!(rejected.cells[1].outcome, CellOutcome::Skipped);
+
+        let accepted = &trace.rules[1];
+        assert!(accepted.matched);
+        assert!(accepted
+            .cells
+            .iter()
+            .all(|c| c.outcome == CellOutcome::Matched));
+
+        let json = serde_json::to_value(&trace).map_err(|e| e.to_string())?;
+        assert_eq!(
+            json["rules"][0]["cells"][1]["outcome"],
+            serde_json::json!("skipped")
+        );
+        Ok(())
+    }
+}
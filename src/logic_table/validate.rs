@@ -0,0 +1,261 @@
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::fmt;
+
+use super::{context_path, Table};
+use crate::context::{get_context_var, set_context_var};
+use crate::expression_parser::decimal::Decimal;
+use crate::expression_parser::temporal::Temporal;
+
+/*
+    Checks the context against the declared input column types before a table
+    runs, so a bad payload is reported instead of silently matching nothing.
+    Columns of types not listed below are not checked.
+*/
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Violation {
+    pub column: String,
+    pub expected: String,
+    /// the context value, null when it is missing
+    pub found: Value,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.found {
+            Value::Null => write!(
+                f,
+                "{}: expected {}, found nothing",
+                self.column, self.expected
+            ),
+            _ => write!(
+                f,
+                "{}: expected {}, found {}",
+                self.column, self.expected, self.found
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Validation {
+    /// values have to be of the declared type already
+    Strict,
+    /// values are converted where it is unambiguous, e.g. "8" to 8 for a number column
+    Coerce,
+}
+
+/// The context to run the table with - coerced in `Validation::Coerce` - or every violation
+pub fn validate_context(
+    table: &Table,
+    context: &Value,
+    validation: Validation,
+) -> Result<Value, Vec<Violation>> {
+    let mut checked = context.clone();
+    let mut violations: Vec<Violation> = vec![];
+
+    // input expressions are computed by the table, only context paths are checked
+    for (name, var_type) in table.defs.inputs.iter() {
+        let Some(name) = context_path(name, context) else {
+            continue;
+        };
+        let value = get_context_var(&name, context);
+        if is_type(&value, var_type) {
+            continue;
+        }
+
+        let coerced = match validation {
+            Validation::Coerce => coerce(&value, var_type),
+            Validation::Strict => None,
+        };
+        match coerced {
+            Some(coerced) => set_context_var(&name, &mut checked, coerced),
+            None => violations.push(Violation {
+                column: name,
+                expected: var_type.clone(),
+                found: value,
+            }),
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(checked)
+    } else {
+        Err(violations)
+    }
+}
+
+const CHECKED_TYPES: [&str; 11] = [
+    "string",
+    "number",
+    "integer",
+    "boolean",
+    "decimal",
+    "date",
+    "time",
+    "date and time",
+    "duration",
+    "list",
+    "context",
+];
+
+fn is_type(value: &Value, var_type: &str) -> bool {
+    match (var_type, value) {
+        ("string", Value::String(_))
+        | ("number", Value::Number(_))
+        | ("boolean", Value::Bool(_))
+        | ("list", Value::Array(_))
+        | ("context", Value::Object(_))
+        | ("decimal", Value::Number(_)) => true,
+        ("integer", Value::Number(n)) => n.is_i64(),
+        ("decimal", Value::String(s)) => Decimal::parse(s).is_ok(),
+        ("date", Value::String(s)) => Temporal::parse_date(s).is_ok(),
+        ("time", Value::String(s)) => Temporal::parse_time(s).is_ok(),
+        ("date and time", Value::String(s)) => Temporal::parse_date_time(s).is_ok(),
+        ("duration", Value::String(s)) => Temporal::parse_duration(s).is_ok(),
+        (_, _) => !CHECKED_TYPES.contains(&var_type),
+    }
+}
+
+fn coerce(value: &Value, var_type: &str) -> Option<Value> {
+    let coerced = match (var_type, value) {
+        ("number", Value::String(s)) => {
+            Value::Number(serde_json::from_str::<Number>(s.trim()).ok()?)
+        }
+        ("integer", Value::String(s)) => {
+            Value::Number(to_integer(&serde_json::from_str::<Number>(s.trim()).ok()?)?)
+        }
+        ("integer", Value::Number(n)) => Value::Number(to_integer(n)?),
+        ("boolean", Value::String(s)) => match s.trim() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => return None,
+        },
+        ("string", Value::Number(n)) => Value::String(n.to_string()),
+        ("string", Value::Bool(b)) => Value::String(b.to_string()),
+        ("decimal", Value::String(s)) => Value::String(Decimal::parse(s.trim()).ok()?.to_string()),
+        _ => return None,
+    };
+
+    is_type(&coerced, var_type).then_some(coerced)
+}
+
+/// Whole numbers within the i64 range, `8.0` becomes `8`
+fn to_integer(n: &Number) -> Option<Number> {
+    if let Some(i) = n.as_i64() {
+        return Some(Number::from(i));
+    }
+    if !n.is_f64() {
+        // an u64 above i64::MAX
+        return None;
+    }
+    let f = n.as_f64()?;
+    // i64::MIN is -2^63 exactly, anything at or above 2^63 does not fit
+    let in_range = f >= i64::MIN as f64 && f < -(i64::MIN as f64);
+    (f.fract() == 0.0 && in_range).then(|| Number::from(f as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::{parse, run_table};
+
+    fn get_test_table() -> Result<Table, String> {
+        let contents = r#"
+        | season   | guestCount | vegetarian | order.date | desiredDish |
+        |----------|------------|------------|------------|------------:|
+        | string   | number     | boolean    | date       |      string |
+        | ##       | ##         | ##         | ##         |          ## |
+        | "Fall"   | <= 8       | -          | -          | "Spaceribs" |
+        "#;
+        parse(contents)
+    }
+
+    #[test]
+    fn reports_violations() -> Result<(), String> {
+        let table = get_test_table()?;
+        let context = serde_json::json!({
+            "season": 3, "guestCount": "8", "order": { "date": "31.01.2024" }
+        });
+
+        let violations = validate_context(&table, &context, Validation::Strict).unwrap_err();
+        let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "season: expected string, found 3",
+                "guestCount: expected number, found \"8\"",
+                "vegetarian: expected boolean, found nothing",
+                "order.date: expected date, found \"31.01.2024\"",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn coerces_values() -> Result<(), String> {
+        let table = get_test_table()?;
+        let context = serde_json::json!({
+            "season": "Fall", "guestCount": "8", "vegetarian": "false", "order": { "date": "2024-01-31" }
+        });
+
+        let checked = validate_context(&table, &context, Validation::Coerce).map_err(|v| {
+            v.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        })?;
+        assert_eq!(checked["guestCount"], serde_json::json!(8));
+        assert_eq!(checked["vegetarian"], serde_json::json!(false));
+        assert_eq!(run_table(&table, &checked)?.len(), 1);
+
+        let context = serde_json::json!({
+            "season": "Fall", "guestCount": "eight", "vegetarian": true, "order": { "date": "2024-01-31" }
+        });
+        let violations = validate_context(&table, &context, Validation::Coerce).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation {
+                column: "guestCount".to_string(),
+                expected: "number".to_string(),
+                found: serde_json::json!("eight"),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn coerces_integers_in_range() -> Result<(), String> {
+        let table = parse(
+            r#"
+        | guests  | dish   |
+        |---------|-------:|
+        | integer | string |
+        | ##      |     ## |
+        | -       | "Stew" |
+        "#,
+        )?;
+        let coerce = |guests: Value| {
+            validate_context(
+                &table,
+                &serde_json::json!({ "guests": guests }),
+                Validation::Coerce,
+            )
+            .map(|checked| checked["guests"].clone())
+            .ok()
+        };
+
+        assert_eq!(coerce(serde_json::json!(8.0)), Some(serde_json::json!(8)));
+        assert_eq!(coerce(serde_json::json!("12")), Some(serde_json::json!(12)));
+        assert_eq!(
+            coerce(serde_json::json!(-9.0e18)),
+            Some(serde_json::json!(-9_000_000_000_000_000_000i64))
+        );
+        assert_eq!(coerce(serde_json::json!(8.5)), None);
+        assert_eq!(coerce(serde_json::json!(1.0e19)), None);
+        assert_eq!(coerce(serde_json::json!("1e300")), None);
+        assert_eq!(coerce(serde_json::json!(u64::MAX)), None);
+        Ok(())
+    }
+}
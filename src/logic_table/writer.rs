@@ -0,0 +1,169 @@
+use super::Table;
+
+/*
+    Writes a table as canonical markdown, the form `parse` reads back:
+    rule id column first, then inputs, outputs and the annotation column,
+    pipes aligned, outputs right aligned and `|` in cells escaped.
+*/
+
+#[derive(Clone, Copy)]
+enum Align {
+    Input,
+    Output,
+    Text,
+}
+
+struct Column {
+    align: Align,
+    cells: Vec<String>,
+}
+
+pub fn to_markdown(table: &Table) -> String {
+    let hit_policy = table.defs.hit_policy.as_deref().unwrap_or("##");
+    let mut columns: Vec<Column> = vec![];
+
+    if let Some(header) = &table.defs.rule_id {
+        columns.push(Column {
+            align: Align::Text,
+            cells: definition(header, "id"),
+        });
+    }
+    for (header, var_type) in table.defs.inputs.iter() {
+        columns.push(Column {
+            align: Align::Input,
+            cells: definition(header, var_type),
+        });
+    }
+    for (header, var_type) in table.defs.outputs.iter() {
+        columns.push(Column {
+            align: Align::Output,
+            cells: definition(header, var_type),
+        });
+    }
+    if let Some(header) = &table.defs.annotation {
+        columns.push(Column {
+            align: Align::Text,
+            cells: definition(header, "string"),
+        });
+    }
+    if let Some(first) = columns.first_mut() {
+        first.cells[2] = hit_policy.to_string();
+    }
+
+    for row in table.rows.iter() {
+        let mut cells: Vec<String> = vec![];
+        if table.defs.rule_id.is_some() {
+            cells.push(row.id.as_deref().map(escape).unwrap_or_default());
+        }
+        cells.extend(row.cells.iter().map(|c| escape(c)));
+        if table.defs.annotation.is_some() {
+            cells.push(row.annotation.as_deref().map(escape).unwrap_or_default());
+        }
+        for (column, cell) in columns.iter_mut().zip(cells) {
+            column.cells.push(cell);
+        }
+    }
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|c| {
+            c.cells
+                .iter()
+                .map(|s| s.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let mut lines: Vec<String> = vec![];
+    let line_count = columns.first().map(|c| c.cells.len()).unwrap_or(0);
+    for i in 0..line_count {
+        let cells: Vec<String> = columns
+            .iter()
+            .zip(widths.iter())
+            .map(|(column, &width)| pad(&column.cells[i], width, column.align))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+
+        if i == 0 {
+            let rule: Vec<String> = columns
+                .iter()
+                .zip(widths.iter())
+                .map(|(column, &width)| match column.align {
+                    Align::Input => "-".repeat(width + 2),
+                    Align::Output => format!("{}:", "-".repeat(width + 1)),
+                    Align::Text => format!(":{}", "-".repeat(width + 1)),
+                })
+                .collect();
+            lines.push(format!("|{}|", rule.join("|")));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Header, type and hit policy row cells of a column
+fn definition(header: &str, var_type: &str) -> Vec<String> {
+    vec![escape(header), escape(var_type), "##".to_string()]
+}
+
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|")
+}
+
+fn pad(cell: &str, width: usize, align: Align) -> String {
+    let fill = " ".repeat(width - cell.chars().count());
+    match align {
+        Align::Output => format!("{}{}", fill, cell),
+        _ => format!("{}{}", cell, fill),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::parse;
+
+    #[test]
+    fn writes_canonical_markdown() -> Result<(), String> {
+        let contents = r#"
+        season|guestCount|rule|desiredDish|notes
+        ---|---|:---|--:|:--
+        string|number|id|string|string
+        U|##|##|##|##
+        "Fall"|<= 8|R-1|"Spaceribs"|ribs \| stew
+        |"Winter"|-|R-2|"Roastbeef"||
+        "#;
+        let table = parse(contents)?;
+
+        let expected = r#"| rule | season   | guestCount | desiredDish | notes        |
+|:-----|----------|------------|------------:|:-------------|
+| id   | string   | number     |      string | string       |
+| U    | ##       | ##         |          ## | ##           |
+| R-1  | "Fall"   | <= 8       | "Spaceribs" | ribs \| stew |
+| R-2  | "Winter" | -          | "Roastbeef" |              |
+"#;
+        let written = to_markdown(&table);
+        assert_eq!(written, expected);
+
+        // round trip
+        let reparsed = parse(&written)?;
+        assert_eq!(reparsed, table);
+        assert_eq!(to_markdown(&reparsed), written);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_sample_table() -> Result<(), String> {
+        let contents = std::fs::read_to_string("./samples/table.md")
+            .expect("Something went wrong reading the TEST file");
+        let table = parse(&contents)?;
+
+        // the sample is already canonical
+        let written = to_markdown(&table);
+        assert_eq!(parse(&written)?, table);
+        assert_eq!(written, contents.trim_start());
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+/*
+    Just enough XML for DMN files: elements, attributes, text, CDATA and the
+    predefined and numeric entities. Comments, processing instructions and
+    the doctype are skipped, namespace prefixes are dropped from element names.
+*/
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Element {
+    /// local name, `dmn:rule` is `rule`
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+/// The root element of the document
+pub(crate) fn parse(xml: &str) -> Result<Element, String> {
+    let mut reader = Reader { xml, pos: 0 };
+
+    reader.skip_misc()?;
+    if !reader.rest().starts_with('<') {
+        return Err(reader.error("expected the root element"));
+    }
+    let root = reader.element()?;
+
+    reader.skip_misc()?;
+    if !reader.rest().is_empty() {
+        return Err(reader.error("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+/// Escapes an attribute value
+pub(crate) fn escape(text: &str) -> String {
+    escape_text(text).replace('"', "&quot;")
+}
+
+/// Escapes element text, quotes stay readable
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+struct Reader<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.xml[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.xml[..self.pos].matches('\n').count() + 1;
+        format!("xml line {}: {}", line, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Moves past `end` and returns everything before it
+    fn take_until(&mut self, end: &str) -> Result<&'a str, String> {
+        match self.rest().find(end) {
+            Some(at) => {
+                let taken = &self.rest()[..at];
+                self.pos += at + end.len();
+                Ok(taken)
+            }
+            None => Err(self.error(&format!("missing \"{}\"", end))),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    /// Whitespace, comments, processing instructions and the doctype between elements
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.take_until("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.take_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || ['/', '>', '=', '<'].contains(&c))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += end;
+        Ok(&rest[..end])
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect('<')?;
+        let qualified = self.name()?;
+        let mut element = Element {
+            name: local_name(qualified).to_string(),
+            attributes: vec![],
+            children: vec![],
+            text: String::new(),
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let key = self.name()?;
+            self.skip_whitespace();
+            self.expect('=')?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.pos += 1;
+            let raw = self.take_until(&quote.to_string())?;
+            let value = unescape(raw).map_err(|e| self.error(&e))?;
+            element.attributes.push((key.to_string(), value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("<{}> is not closed", qualified)));
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                let closing = self.name()?;
+                if closing != qualified {
+                    return Err(
+                        self.error(&format!("expected </{}>, found </{}>", qualified, closing))
+                    );
+                }
+                self.skip_whitespace();
+                self.expect('>')?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                element.text.push_str(self.take_until("]]>")?);
+            } else if rest.starts_with("<?") {
+                self.take_until("?>")?;
+            } else if rest.starts_with('<') {
+                element.children.push(self.element()?);
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let text = unescape(&rest[..end]).map_err(|e| self.error(&e))?;
+                element.text.push_str(&text);
+                self.pos += end;
+            }
+        }
+    }
+}
+
+fn local_name(qualified: &str) -> &str {
+    qualified.rsplit(':').next().unwrap_or(qualified)
+}
+
+fn unescape(raw: &str) -> Result<String, String> {
+    let mut text = String::new();
+    let mut rest = raw;
+
+    while let Some(at) = rest.find('&') {
+        text.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let end = rest
+            .find(';')
+            .ok_or_else(|| "unterminated entity".to_string())?;
+        let entity = &rest[1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(decimal) = entity.strip_prefix('#') {
+                    decimal.parse::<u32>().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| format!("unknown entity \"&{};\"", entity))?
+            }
+        };
+        text.push(c);
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_elements_attributes_and_text() -> Result<(), String> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <!-- exported -->
+        <dmn:definitions xmlns:dmn="https://www.omg.org/spec/DMN/20191111/MODEL/" id='d&amp;1'>
+          <dmn:text>&lt; 8 &#38; &#x41;</dmn:text>
+          <dmn:text><![CDATA["a" < "b"]]></dmn:text>
+          <dmn:empty />
+        </dmn:definitions>"#;
+
+        let root = parse(xml)?;
+        assert_eq!(root.name, "definitions");
+        assert_eq!(root.attribute("id"), Some("d&1"));
+        let texts: Vec<&str> = root
+            .children_named("text")
+            .map(|t| t.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["< 8 & A", "\"a\" < \"b\""]);
+        assert!(root.child("empty").is_some_and(|e| e.children.is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_broken_xml() {
+        let err = parse("<a>\n<b></a>").unwrap_err();
+        assert_eq!(err, "xml line 2: expected </b>, found </a>");
+
+        let err = parse("<a>&nbsp;</a>").unwrap_err();
+        assert!(err.contains("unknown entity \"&nbsp;\""), "{}", err);
+
+        let err = parse("<a>").unwrap_err();
+        assert!(err.contains("<a> is not closed"), "{}", err);
+    }
+}
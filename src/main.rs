@@ -1,17 +1,233 @@
-extern crate serde_json;
-
-use std::time::SystemTime;
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::exit;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
+use md_logic::json_logic::AllCombined;
+use md_logic::logic_table::coverage::document_coverage;
+use md_logic::logic_table::csv::{parse_csv, parse_tsv};
+use md_logic::logic_table::dmn::{parse_dmn, to_dmn};
+use md_logic::logic_table::document::{format_document, parse_document, write_document};
+use md_logic::logic_table::html::document_to_html;
+use md_logic::logic_table::test_cases::test_document;
+use md_logic::logic_table::writer::to_markdown;
+use md_logic::service::http;
+use md_logic::service::watch::WatchedRepository;
 use serde_json::Value;
 
-use crate::json_logic::AllCombined;
-mod context;
-mod expression_parser;
-mod logic_table;
-
-mod json_logic;
+const USAGE: &str = "usage:
+  md_logic fmt [--check] <file.md>...
+  md_logic test <file.md>...
+  md_logic coverage [--json] <file.md>...
+  md_logic html <file.md> [<context.json>]
+  md_logic from-dmn <file.dmn>
+  md_logic to-dmn <file.md>
+  md_logic from-csv <file.csv|file.tsv>
+  md_logic serve <dir> [<address>]";
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None => benchmark(),
+        Some("fmt") => exit(fmt(&args[1..])),
+        Some("test") if args.len() > 1 => exit(test(&args[1..])),
+        Some("coverage") if args.len() > 1 => exit(coverage(&args[1..])),
+        Some("html") if args.len() == 2 || args.len() == 3 => exit(html(&args[1], args.get(2))),
+        Some("from-dmn") if args.len() == 2 => exit(convert(&args[1], |contents| {
+            Ok(write_document(&parse_dmn(contents)?))
+        })),
+        Some("to-dmn") if args.len() == 2 => exit(convert(&args[1], |contents| {
+            to_dmn(&parse_document(contents)?)
+        })),
+        Some("from-csv") if args.len() == 2 => {
+            let tsv = args[1].ends_with(".tsv");
+            exit(convert(&args[1], |contents| {
+                let table = if tsv {
+                    parse_tsv(contents)?
+                } else {
+                    parse_csv(contents)?
+                };
+                Ok(to_markdown(&table))
+            }))
+        }
+        Some("serve") if args.len() == 2 || args.len() == 3 => exit(serve(
+            &args[1],
+            args.get(2).map_or("127.0.0.1:8080", String::as_str),
+        )),
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+}
+
+/// Rewrites the tables of every file in canonical form, `--check` only lists files that would change
+fn fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|a| a == "--check");
+    let files: Vec<&String> = args.iter().filter(|a| *a != "--check").collect();
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+
+    let mut code = 0;
+    for file in files {
+        let formatted = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Ok((format_document(&contents)?, contents)));
+        match formatted {
+            Ok((formatted, contents)) if formatted == contents => {}
+            Ok(_) if check => {
+                println!("{}", file);
+                code = 1;
+            }
+            Ok((formatted, _)) => {
+                if let Err(e) = fs::write(file, formatted) {
+                    eprintln!("{}: {}", file, e);
+                    code = 1;
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// Runs the test cases of every file, fails when one of them does
+fn test(files: &[String]) -> i32 {
+    let (mut passed, mut failed) = (0, 0);
+
+    for file in files {
+        let results = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| test_document(&contents));
+        match results {
+            Ok(results) => {
+                for result in results {
+                    println!("{}: {}", file, result);
+                    if result.passed() {
+                        passed += 1;
+                    } else {
+                        failed += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", passed, failed);
+    i32::from(failed > 0)
+}
+
+/// Reports the rules the test cases of every file never hit, fails when there are any
+fn coverage(args: &[String]) -> i32 {
+    let json = args.iter().any(|a| a == "--json");
+    let mut report = serde_json::Map::new();
+    let mut code = 0;
+
+    for file in args.iter().filter(|a| *a != "--json") {
+        let coverages = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| document_coverage(&contents));
+        match coverages {
+            Ok(coverages) => {
+                if coverages.iter().any(|c| {
+                    c.coverage.uncovered().next().is_some() || !c.coverage.errors.is_empty()
+                }) {
+                    code = 1;
+                }
+                if json {
+                    report.insert(file.clone(), serde_json::json!(coverages));
+                } else {
+                    for coverage in coverages {
+                        println!("{}: {}", file, coverage);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                code = 1;
+            }
+        }
+    }
+
+    if json {
+        println!("{}", Value::Object(report));
+    }
+    code
+}
+
+/// Prints the decisions of a file as a HTML page, highlighting the rules the context matches
+fn html(file: &str, context_file: Option<&String>) -> i32 {
+    let context = match context_file {
+        Some(context_file) => match fs::read_to_string(context_file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Value>(&json).map_err(|e| e.to_string()))
+        {
+            Ok(context) => Some(context),
+            Err(e) => {
+                eprintln!("{}: {}", context_file, e);
+                return 1;
+            }
+        },
+        None => None,
+    };
+
+    convert(file, |contents| {
+        document_to_html(file, parse_document(contents)?, context.as_ref())
+    })
+}
+
+/// Answers HTTP requests with the decisions and rules of the directory, reloading files as they change
+fn serve(dir: &str, address: &str) -> i32 {
+    let repository = match WatchedRepository::load(Path::new(dir)) {
+        Ok(repository) => Arc::new(repository),
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("{}: {}", address, e);
+            return 1;
+        }
+    };
+    println!("serving {} on http://{}", dir, address);
+    repository.watch(Duration::from_secs(1), |reload| eprintln!("{}", reload));
+    http::serve(listener, repository)
+}
+
+/// Prints the converted file
+fn convert(file: &str, conversion: impl Fn(&str) -> Result<String, String>) -> i32 {
+    match fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| conversion(&contents))
+    {
+        Ok(converted) => {
+            print!("{}", converted);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            1
+        }
+    }
+}
+
+fn benchmark() {
     const DATA: &str = r#" 
     {
       "and": [
@@ -0,0 +1,416 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::json_logic::{AllCombined, Environment};
+
+/*
+    BPMN like processes, defined in JSON:
+    - task: sets context variables from JsonLogic expressions
+    - decision: runs a decision table, its outputs are merged into the context
+    - exclusiveGateway: follows the first flow whose condition is true,
+      the one flow without condition is the default when none is
+    - userTask: waits - the instance is returned to be stored, and `resume`
+      continues it later with the completion data of the task
+    - end: finishes the process
+*/
+
+pub mod store;
+
+/// Guards against processes that loop forever
+const MAX_STEPS: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Process {
+    pub id: String,
+    pub start: String,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Node {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: NodeKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NodeKind {
+    Task {
+        #[serde(default)]
+        set: BTreeMap<String, AllCombined>,
+        next: String,
+    },
+    Decision {
+        decision: String,
+        next: String,
+    },
+    ExclusiveGateway {
+        flows: Vec<Flow>,
+    },
+    UserTask {
+        next: String,
+    },
+    End,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Flow {
+    pub condition: Option<AllCombined>,
+    pub next: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Instance {
+    /// id of the process definition
+    pub process: String,
+    pub status: Status,
+    pub context: Value,
+    /// ids of the visited nodes, in order
+    pub path: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    /// stopped at the user task `node`
+    Waiting {
+        node: String,
+    },
+    Completed,
+}
+
+impl Process {
+    pub fn parse(contents: &str) -> Result<Process, String> {
+        let process: Process = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+        for (index, node) in process.nodes.iter().enumerate() {
+            if process.nodes[..index].iter().any(|n| n.id == node.id) {
+                return Err(format!("duplicate node id \"{}\"", node.id));
+            }
+        }
+
+        process.node(&process.start)?;
+        for node in process.nodes.iter() {
+            let targets: Vec<&String> = match &node.kind {
+                NodeKind::Task { next, .. }
+                | NodeKind::Decision { next, .. }
+                | NodeKind::UserTask { next } => vec![next],
+                NodeKind::ExclusiveGateway { flows } => {
+                    if flows.iter().filter(|f| f.condition.is_none()).count() > 1 {
+                        return Err(format!(
+                            "gateway \"{}\": more than one default flow",
+                            node.id
+                        ));
+                    }
+                    flows.iter().map(|f| &f.next).collect()
+                }
+                NodeKind::End => vec![],
+            };
+            for target in targets {
+                process
+                    .node(target)
+                    .map_err(|e| format!("node \"{}\": {}", node.id, e))?;
+            }
+        }
+
+        Ok(process)
+    }
+
+    /// Walks the process from `start` until an end event or a user task
+    pub fn run(&self, context: Value, env: &Environment) -> Result<Instance, String> {
+        let context = match context {
+            Value::Object(_) => context,
+            Value::Null => Value::Object(Default::default()),
+            _ => return Err("process context has to be an object".to_string()),
+        };
+        let instance = Instance {
+            process: self.id.clone(),
+            status: Status::Completed,
+            context,
+            path: vec![],
+        };
+
+        self.walk(instance, self.node(&self.start)?, env)
+    }
+
+    /// Completes the user task `instance` waits at, `completion` is merged into the context
+    pub fn resume(
+        &self,
+        mut instance: Instance,
+        completion: Value,
+        env: &Environment,
+    ) -> Result<Instance, String> {
+        if instance.process != self.id {
+            return Err(format!(
+                "instance belongs to process \"{}\", not \"{}\"",
+                instance.process, self.id
+            ));
+        }
+        let Status::Waiting { node } = &instance.status else {
+            return Err("instance is not waiting at a user task".to_string());
+        };
+        let NodeKind::UserTask { next } = &self.node(node)?.kind else {
+            return Err(format!("node \"{}\" is not a user task", node));
+        };
+
+        match (completion, &mut instance.context) {
+            (Value::Object(values), Value::Object(context)) => context.extend(values),
+            (Value::Null, _) => {}
+            _ => return Err("task completion data has to be an object".to_string()),
+        }
+
+        self.walk(instance, self.node(next)?, env)
+    }
+
+    fn walk<'a>(
+        &'a self,
+        mut instance: Instance,
+        mut current: &'a Node,
+        env: &Environment,
+    ) -> Result<Instance, String> {
+        let mut steps = 0;
+        loop {
+            if steps >= MAX_STEPS {
+                return Err(format!(
+                    "process \"{}\" did not end after {} steps",
+                    self.id, MAX_STEPS
+                ));
+            }
+            steps += 1;
+            instance.path.push(current.id.clone());
+
+            let next = match &current.kind {
+                NodeKind::Task { set, next } => {
+                    let values: Vec<(String, Value)> = set
+                        .iter()
+                        .map(|(key, expression)| {
+                            (
+                                key.clone(),
+                                to_value(expression.execute_with(&instance.context, env)),
+                            )
+                        })
+                        .collect();
+                    if let Value::Object(context) = &mut instance.context {
+                        context.extend(values);
+                    }
+                    next
+                }
+                NodeKind::Decision { decision, next } => {
+                    let Some(decisions) = env.decisions else {
+                        return Err(format!("node \"{}\": no decisions registered", current.id));
+                    };
+                    decisions
+                        .evaluate_into(decision, &mut instance.context)
+                        .map_err(|e| format!("node \"{}\": {}", current.id, e))?;
+                    next
+                }
+                NodeKind::ExclusiveGateway { flows } => {
+                    let flow = flows
+                        .iter()
+                        .find(|flow| {
+                            flow.condition.as_ref().is_some_and(|condition| {
+                                matches!(
+                                    condition.execute_with(&instance.context, env),
+                                    AllCombined::Primitive(Value::Bool(true))
+                                )
+                            })
+                        })
+                        .or_else(|| flows.iter().find(|flow| flow.condition.is_none()));
+                    match flow {
+                        Some(flow) => &flow.next,
+                        None => return Err(format!("gateway \"{}\": no flow matches", current.id)),
+                    }
+                }
+                NodeKind::UserTask { .. } => {
+                    instance.status = Status::Waiting {
+                        node: current.id.clone(),
+                    };
+                    return Ok(instance);
+                }
+                NodeKind::End => {
+                    instance.status = Status::Completed;
+                    return Ok(instance);
+                }
+            };
+
+            current = self.node(next)?;
+        }
+    }
+
+    fn node(&self, id: &str) -> Result<&Node, String> {
+        self.nodes
+            .iter()
+            .find(|n| n.id == id)
+            .ok_or_else(|| format!("unknown node \"{}\"", id))
+    }
+}
+
+/// Task results are stored as plain JSON, a list result becomes an array
+fn to_value(result: AllCombined) -> Value {
+    match result {
+        AllCombined::Primitive(v) => v,
+        AllCombined::OpList(l) => Value::Array(l.into_iter().map(to_value).collect()),
+        AllCombined::Decimal(d) => d.to_number().map_or(Value::Null, Value::Number),
+        AllCombined::Ops(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_table::document::parse_document;
+    use crate::logic_table::graph::DecisionGraph;
+    use std::fs;
+
+    const RISK: &str = r#"
+## Risk
+
+| amount | risk   |
+|--------|-------:|
+| number | string |
+| ##     | ##     |
+| < 1000 | "low"  |
+| >= 1000 | "high" |
+"#;
+
+    fn get_test_process() -> Result<Process, String> {
+        let contents = fs::read_to_string("./samples/approval.json")
+            .expect("Something went wrong reading the TEST file");
+
+        Process::parse(&contents)
+    }
+
+    #[test]
+    fn runs_process_paths() -> Result<(), String> {
+        let process = get_test_process()?;
+        let decisions = DecisionGraph::new(parse_document(RISK)?)?;
+        let env = Environment {
+            decisions: Some(&decisions),
+            ..Default::default()
+        };
+
+        let instance = process.run(serde_json::json!({ "amount": 200 }), &env)?;
+        assert_eq!(instance.path, vec!["classify", "route", "approve", "done"]);
+        assert_eq!(
+            instance.context,
+            serde_json::json!({ "amount": 200, "risk": "low", "approved": true })
+        );
+
+        let instance = process.run(serde_json::json!({ "amount": 20000 }), &env)?;
+        assert_eq!(instance.path, vec!["classify", "route", "reject", "done"]);
+
+        let instance = process.run(serde_json::json!({ "amount": 5000 }), &env)?;
+        assert_eq!(instance.path, vec!["classify", "route", "review", "done"]);
+        assert_eq!(instance.context["reviewFee"], serde_json::json!(50.0));
+
+        let no_decisions = process.run(
+            serde_json::json!({ "amount": 200 }),
+            &Environment::default(),
+        );
+        assert!(no_decisions.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn waits_at_user_tasks_and_resumes() -> Result<(), String> {
+        let contents = r#"{ "id": "expense", "start": "submit", "nodes": [
+            { "id": "submit", "type": "task", "set": { "submitted": true }, "next": "approve" },
+            { "id": "approve", "type": "userTask", "next": "route" },
+            { "id": "route", "type": "exclusiveGateway", "flows": [
+                { "condition": { "var": "approved" }, "next": "pay" },
+                { "next": "done" }
+            ] },
+            { "id": "pay", "type": "task", "set": { "paid": { "var": "amount" } }, "next": "done" },
+            { "id": "done", "type": "end" }
+        ] }"#;
+        let process = Process::parse(contents)?;
+        let env = Environment::default();
+
+        let instance = process.run(serde_json::json!({ "amount": 40 }), &env)?;
+        assert_eq!(
+            instance.status,
+            Status::Waiting {
+                node: "approve".to_string()
+            }
+        );
+        assert_eq!(instance.path, vec!["submit", "approve"]);
+
+        // survives a round trip through JSON, e.g. a restart
+        let stored = serde_json::to_string(&instance).map_err(|e| e.to_string())?;
+        let instance: Instance = serde_json::from_str(&stored).map_err(|e| e.to_string())?;
+
+        let instance = process.resume(instance, serde_json::json!({ "approved": true }), &env)?;
+        assert_eq!(instance.status, Status::Completed);
+        assert_eq!(
+            instance.path,
+            vec!["submit", "approve", "route", "pay", "done"]
+        );
+        assert_eq!(instance.context["paid"], serde_json::json!(40));
+
+        let err = process.resume(instance, Value::Null, &env).unwrap_err();
+        assert_eq!(err, "instance is not waiting at a user task");
+        Ok(())
+    }
+
+    #[test]
+    fn takes_default_flow_last() -> Result<(), String> {
+        let contents = r#"{ "id": "p", "start": "g", "nodes": [
+            { "id": "g", "type": "exclusiveGateway", "flows": [
+                { "next": "usual" },
+                { "condition": { "var": "urgent" }, "next": "fast" }
+            ] },
+            { "id": "usual", "type": "end" },
+            { "id": "fast", "type": "end" }
+        ] }"#;
+        let process = Process::parse(contents)?;
+
+        let env = Environment::default();
+        let instance = process.run(serde_json::json!({ "urgent": true }), &env)?;
+        assert_eq!(instance.path, vec!["g", "fast"]);
+        let instance = process.run(serde_json::json!({ "urgent": false }), &env)?;
+        assert_eq!(instance.path, vec!["g", "usual"]);
+        Ok(())
+    }
+
+    #[test]
+    fn fails_on_invalid_processes() {
+        let unknown_target = r#"{ "id": "p", "start": "a", "nodes": [
+            { "id": "a", "type": "task", "next": "b" }
+        ] }"#;
+        assert_eq!(
+            Process::parse(unknown_target).unwrap_err(),
+            "node \"a\": unknown node \"b\""
+        );
+
+        let no_flow = r#"{ "id": "p", "start": "g", "nodes": [
+            { "id": "g", "type": "exclusiveGateway", "flows": [
+                { "condition": { "var": "go" }, "next": "e" }
+            ] },
+            { "id": "e", "type": "end" }
+        ] }"#;
+        let process = Process::parse(no_flow).expect("process should parse");
+        let err = process
+            .run(Value::Null, &Environment::default())
+            .unwrap_err();
+        assert_eq!(err, "gateway \"g\": no flow matches");
+
+        let two_defaults = r#"{ "id": "p", "start": "g", "nodes": [
+            { "id": "g", "type": "exclusiveGateway", "flows": [
+                { "next": "e" }, { "next": "e" }
+            ] },
+            { "id": "e", "type": "end" }
+        ] }"#;
+        assert_eq!(
+            Process::parse(two_defaults).unwrap_err(),
+            "gateway \"g\": more than one default flow"
+        );
+
+        let endless = r#"{ "id": "p", "start": "a", "nodes": [
+            { "id": "a", "type": "task", "next": "a" }
+        ] }"#;
+        let process = Process::parse(endless).expect("process should parse");
+        assert!(process.run(Value::Null, &Environment::default()).is_err());
+    }
+}
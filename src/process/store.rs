@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::Instance;
+
+/// Keeps waiting process instances between `Process::run` and `Process::resume`
+pub trait InstanceStore {
+    fn save(&self, id: &str, instance: &Instance) -> Result<(), String>;
+    fn load(&self, id: &str) -> Result<Instance, String>;
+    fn remove(&self, id: &str) -> Result<(), String>;
+}
+
+/// Default store, one `<id>.json` file per instance in `dir`
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+        Ok(FileStore { dir })
+    }
+
+    /// Ids become file names, so only letters, digits, `-` and `_` are allowed
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("invalid instance id \"{}\"", id));
+        }
+
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+impl InstanceStore for FileStore {
+    fn save(&self, id: &str, instance: &Instance) -> Result<(), String> {
+        let path = self.path(id)?;
+        let contents = serde_json::to_string_pretty(instance).map_err(|e| e.to_string())?;
+
+        // write then rename, a crash never leaves a half written instance
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, contents).map_err(|e| format!("{}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn load(&self, id: &str) -> Result<Instance, String> {
+        let path = self.path(id)?;
+        let contents =
+            fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn remove(&self, id: &str) -> Result<(), String> {
+        let path = self.path(id)?;
+        fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Status;
+
+    #[test]
+    fn saves_and_loads_instances() -> Result<(), String> {
+        let dir = std::env::temp_dir().join(format!("md_logic_store_{}", std::process::id()));
+        let store = FileStore::new(&dir)?;
+        let instance = Instance {
+            process: "expense".to_string(),
+            status: Status::Waiting {
+                node: "approve".to_string(),
+            },
+            context: serde_json::json!({ "amount": 40 }),
+            path: vec!["submit".to_string(), "approve".to_string()],
+        };
+
+        store.save("order-1", &instance)?;
+        assert_eq!(store.load("order-1")?, instance);
+
+        store.remove("order-1")?;
+        assert!(store.load("order-1").is_err());
+        assert!(store.save("../escape", &instance).is_err());
+
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::watch::WatchedRepository;
+use super::Response;
+
+/*
+    Just enough HTTP/1.1 for JSON requests from other services: one request
+    per connection, the body is read by Content-Length, answers close the
+    connection. Every connection gets its own thread, failures are logged
+    to stderr and never stop the server.
+*/
+
+/// larger bodies are rejected
+const MAX_BODY: usize = 1024 * 1024;
+/// longer request or header lines are rejected
+const MAX_LINE: usize = 8 * 1024;
+/// requests with more headers are rejected
+const MAX_HEADERS: usize = 100;
+/// a client that sends nothing for this long is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+/// Answers requests forever, each with the repository current when it arrived
+pub fn serve(listener: TcpListener, repository: Arc<WatchedRepository>) -> ! {
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("accepting a connection failed: {}", e);
+                continue;
+            }
+        };
+        let repository = Arc::clone(&repository);
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map_or("unknown peer".to_string(), |a| a.to_string());
+            if let Err(e) = handle_connection(stream, &repository.current()) {
+                eprintln!("{}: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, repository: &super::Repository) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let response = match read_request(&mut BufReader::new(&mut stream)) {
+        Ok(request) => repository.handle(&request.method, &request.path, &request.body),
+        Err(e) => Response {
+            status: 400,
+            body: serde_json::json!({ "error": e }),
+        },
+    };
+    write_response(&mut stream, &response)
+}
+
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, String> {
+    let line = read_line(reader)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("invalid request line".to_string());
+    };
+    // the query string is not used
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let method = method.to_string();
+
+    let mut content_length = 0;
+    for count in 0.. {
+        let header = read_line(reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(format!("more than {} headers", MAX_HEADERS));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| "invalid Content-Length".to_string())?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(format!("body is larger than {} bytes", MAX_BODY));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "body is not UTF-8".to_string())?;
+
+    Ok(Request { method, path, body })
+}
+
+/// One line of at most MAX_LINE bytes
+fn read_line(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE as u64 + 1)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    if line.len() > MAX_LINE {
+        return Err(format!("line is longer than {} bytes", MAX_LINE));
+    }
+    Ok(line)
+}
+
+pub fn write_response(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        422 => "Unprocessable Entity",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::tests::repository_dir;
+    use std::fs;
+    use std::io::Cursor;
+
+    fn request(address: &str, raw: &str) -> Result<String, String> {
+        let mut stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+        stream
+            .write_all(raw.as_bytes())
+            .map_err(|e| e.to_string())?;
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .map_err(|e| e.to_string())?;
+        Ok(response)
+    }
+
+    #[test]
+    fn answers_on_localhost() -> Result<(), String> {
+        let dir = repository_dir("http");
+        let repository = Arc::new(WatchedRepository::load(&dir)?);
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| e.to_string())?
+            .to_string();
+        thread::spawn(move || serve(listener, repository));
+
+        let body = r#"{"season":"Fall","guestCount":12}"#;
+        let response = request(
+            &address,
+            &format!(
+                "POST /decisions/Dish/evaluate HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        )?;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with(r#"{"results":[{"desiredDish":"Stew"}]}"#));
+
+        let response = request(&address, "GET /decisions?verbose HTTP/1.1\r\n\r\n")?;
+        assert!(response.contains("\"rules\":[\"adult\"]"), "{}", response);
+
+        let response = request(&address, "garbage\r\n\r\n")?;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_long_lines_and_many_headers() {
+        let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        let err = read_request(&mut Cursor::new(long)).err();
+        assert_eq!(err, Some(format!("line is longer than {} bytes", MAX_LINE)));
+
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-A: 1\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let err = read_request(&mut Cursor::new(many)).err();
+        assert_eq!(err, Some(format!("more than {} headers", MAX_HEADERS)));
+
+        let enough = format!("GET / HTTP/1.1\r\n{}\r\n", "X-A: 1\r\n".repeat(MAX_HEADERS));
+        assert!(read_request(&mut Cursor::new(enough)).is_ok());
+    }
+}
@@ -0,0 +1,339 @@
+pub mod http;
+pub mod watch;
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::json_logic::{AllCombined, Environment};
+use crate::logic_table::document::{parse_document, Decision};
+use crate::logic_table::graph::DecisionGraph;
+
+/*
+    Decisions and JsonLogic rules served over HTTP, see `http::serve`.
+
+    GET  /decisions                  names, inputs and outputs of every decision, names of the rules
+    POST /decisions/{name}/evaluate  context in, `{"results": [outputs of every matching rule]}` out
+    POST /jsonlogic/evaluate         `{"rule": "name" or a rule, "data": context}` in, `{"result": ..}` out
+
+    Errors are `{"error": message}` with a 4xx status. `watch::WatchedRepository`
+    keeps the repository in step with its directory.
+*/
+
+/// Every decision and rule of a directory
+pub struct Repository {
+    pub graph: DecisionGraph,
+    /// JsonLogic rules by file name without extension
+    pub rules: BTreeMap<String, AllCombined>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+/// What one file of the directory holds
+enum Source {
+    Decisions(Vec<Decision>),
+    Rule(String, AllCombined),
+}
+
+impl Repository {
+    /// Decisions from every `.md` file and rules from every `.json` file of `dir`
+    pub fn load(dir: &Path) -> Result<Repository, String> {
+        let mut sources: Vec<(PathBuf, String)> = vec![];
+        for path in source_files(dir)? {
+            let contents =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            sources.push((path, contents));
+        }
+        Repository::from_sources(&sources)
+    }
+
+    /// Decisions and rules from the contents of `.md` and `.json` files
+    pub fn from_sources(sources: &[(PathBuf, String)]) -> Result<Repository, String> {
+        let mut decisions: Vec<Decision> = vec![];
+        let mut rules: BTreeMap<String, AllCombined> = BTreeMap::new();
+        for (path, contents) in sources {
+            match parse_source(path, contents).map_err(|e| format!("{}: {}", path.display(), e))? {
+                Source::Decisions(parsed) => decisions.extend(parsed),
+                Source::Rule(name, rule) => {
+                    rules.insert(name, rule);
+                }
+            }
+        }
+
+        Ok(Repository {
+            graph: DecisionGraph::new(decisions)?,
+            rules,
+        })
+    }
+
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> Response {
+        let segments: Vec<String> = path
+            .trim_matches('/')
+            .split('/')
+            .map(percent_decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match (method, segments.as_slice()) {
+            ("GET", ["decisions"]) => self.list(),
+            ("POST", ["decisions", name, "evaluate"]) => self.evaluate_decision(name, body),
+            ("POST", ["jsonlogic", "evaluate"]) => self.evaluate_rule(body),
+            (_, ["decisions"] | ["decisions", _, "evaluate"] | ["jsonlogic", "evaluate"]) => {
+                error(405, format!("method {} is not allowed", method))
+            }
+            _ => error(404, format!("no route for {}", path)),
+        }
+    }
+
+    fn list(&self) -> Response {
+        let decisions: Vec<Value> = self
+            .graph
+            .decisions()
+            .iter()
+            .map(|d| {
+                let names = |columns: &[(String, String)]| {
+                    columns
+                        .iter()
+                        .map(|(name, _)| name.clone())
+                        .collect::<Vec<_>>()
+                };
+                serde_json::json!({
+                    "name": d.name,
+                    "inputs": names(&d.table.defs.inputs),
+                    "outputs": names(&d.table.defs.outputs),
+                })
+            })
+            .collect();
+        let rules: Vec<&String> = self.rules.keys().collect();
+
+        Response {
+            status: 200,
+            body: serde_json::json!({ "decisions": decisions, "rules": rules }),
+        }
+    }
+
+    fn evaluate_decision(&self, name: &str, body: &str) -> Response {
+        if !self.graph.decisions().iter().any(|d| d.name == name) {
+            return error(404, format!("no decision named \"{}\"", name));
+        }
+        let context = match parse_body(body) {
+            Ok(context) => context,
+            Err(response) => return response,
+        };
+
+        match self.graph.evaluate(name, &context) {
+            Ok(results) => {
+                let results: Vec<Value> = results
+                    .into_iter()
+                    .map(|result| {
+                        Value::Object(
+                            result
+                                .into_iter()
+                                .map(|(key, operand)| (key, operand.to_value()))
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                Response {
+                    status: 200,
+                    body: serde_json::json!({ "results": results }),
+                }
+            }
+            Err(e) => error(422, e),
+        }
+    }
+
+    fn evaluate_rule(&self, body: &str) -> Response {
+        let request = match parse_body(body) {
+            Ok(request) => request,
+            Err(response) => return response,
+        };
+        let data = request.get("data").cloned().unwrap_or(Value::Null);
+
+        let inline: AllCombined;
+        let rule = match request.get("rule") {
+            Some(Value::String(name)) => match self.rules.get(name) {
+                Some(rule) => rule,
+                None => return error(404, format!("no rule named \"{}\"", name)),
+            },
+            Some(rule) => match serde_json::from_value(rule.clone()) {
+                Ok(rule) => {
+                    inline = rule;
+                    &inline
+                }
+                Err(e) => return error(400, format!("invalid rule: {}", e)),
+            },
+            None => return error(400, "\"rule\" is missing".to_string()),
+        };
+
+        let env = Environment {
+            decisions: Some(&self.graph),
+            ..Default::default()
+        };
+        let result = rule.execute_with(&data, &env);
+        match serde_json::to_value(&result) {
+            Ok(result) => Response {
+                status: 200,
+                body: serde_json::json!({ "result": result }),
+            },
+            Err(e) => error(422, e.to_string()),
+        }
+    }
+}
+
+/// The `.md` and `.json` files of `dir`, sorted by path
+fn source_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == "md" || extension == "json")
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// A `.md` file holds decisions, any other a JsonLogic rule named after the file
+fn parse_source(path: &Path, contents: &str) -> Result<Source, String> {
+    if path.extension().is_some_and(|extension| extension == "md") {
+        return Ok(Source::Decisions(parse_document(contents)?));
+    }
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let rule = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    Ok(Source::Rule(name, rule))
+}
+
+fn error(status: u16, message: String) -> Response {
+    Response {
+        status,
+        body: serde_json::json!({ "error": message }),
+    }
+}
+
+/// An empty body is an empty context
+fn parse_body(body: &str) -> Result<Value, Response> {
+    if body.trim().is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    serde_json::from_str(body).map_err(|e| error(400, format!("invalid JSON: {}", e)))
+}
+
+/// `Dish%20Size` is `Dish Size`, invalid escapes are kept as they are
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded: Vec<u8> = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn repository_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("md_logic_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).expect("temp dir");
+        fs::copy("./samples/decisions.md", dir.join("decisions.md")).expect("sample");
+        fs::write(
+            dir.join("adult.json"),
+            r#"{ ">=": [{ "var": "age" }, 18] }"#,
+        )
+        .expect("rule");
+        fs::write(dir.join("notes.txt"), "not a rule").expect("notes");
+        dir
+    }
+
+    #[test]
+    fn serves_decisions_and_rules() -> Result<(), String> {
+        let dir = repository_dir("service");
+        let repository = Repository::load(&dir)?;
+
+        let list = repository.handle("GET", "/decisions", "");
+        assert_eq!(list.status, 200);
+        assert_eq!(list.body["decisions"][0]["name"], serde_json::json!("Dish"));
+        assert_eq!(list.body["rules"], serde_json::json!(["adult"]));
+
+        let dish = repository.handle(
+            "POST",
+            "/decisions/Beverage/evaluate",
+            r#"{ "season": "Fall", "guestCount": 4 }"#,
+        );
+        assert_eq!(
+            dish.body,
+            serde_json::json!({ "results": [{ "beverage": "Cider" }] })
+        );
+
+        let adult = repository.handle(
+            "POST",
+            "/jsonlogic/evaluate",
+            r#"{ "rule": "adult", "data": { "age": 20 } }"#,
+        );
+        assert_eq!(adult.body, serde_json::json!({ "result": true }));
+
+        let inline = repository.handle(
+            "POST",
+            "/jsonlogic/evaluate",
+            r#"{ "rule": { "decide": ["Dish", { "var": "order" }] }, "data": { "order": { "season": "Fall", "guestCount": 12 } } }"#,
+        );
+        assert_eq!(
+            inline.body,
+            serde_json::json!({ "result": [{ "desiredDish": "Stew" }] })
+        );
+
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_errors_with_status() -> Result<(), String> {
+        let dir = repository_dir("service_errors");
+        let repository = Repository::load(&dir)?;
+
+        let statuses: Vec<u16> = [
+            ("POST", "/decisions/Lunch/evaluate", "{}"),
+            ("POST", "/decisions/Dish/evaluate", "{ not json"),
+            ("POST", "/decisions/Dish/evaluate", "[1]"),
+            ("GET", "/decisions/Dish/evaluate", ""),
+            ("POST", "/jsonlogic/evaluate", "{}"),
+            ("GET", "/", ""),
+        ]
+        .iter()
+        .map(|(method, path, body)| repository.handle(method, path, body).status)
+        .collect();
+        assert_eq!(statuses, vec![404, 400, 422, 405, 400, 404]);
+
+        assert_eq!(percent_decode("Dish%20Size%2"), "Dish Size%2");
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use super::{parse_source, source_files, Repository};
+
+/*
+    A repository that follows its directory, so analysts can change rules
+    without a redeploy. `reload` compares size and modification time of
+    every file with the last look, reads what changed and swaps in a new
+    `Repository` at once - requests already running keep the one they
+    started with. A file that no longer parses keeps its last good version,
+    as does every changed file when the decisions no longer fit together -
+    those are read again on every reload until they do.
+*/
+
+pub struct WatchedRepository {
+    dir: PathBuf,
+    current: RwLock<Arc<Repository>>,
+    files: Mutex<Files>,
+}
+
+#[derive(Default)]
+struct Files {
+    /// size and modification time of every file when it was last read
+    stamps: BTreeMap<PathBuf, Stamp>,
+    /// the last version of every file that parsed
+    sources: BTreeMap<PathBuf, String>,
+}
+
+type Stamp = (u64, Option<SystemTime>);
+
+/// What a reload changed
+#[derive(Debug, Default, PartialEq)]
+pub struct Reload {
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// files that kept their last good version, and why
+    pub errors: Vec<String>,
+}
+
+impl Reload {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty() && self.errors.is_empty()
+    }
+}
+
+impl fmt::Display for Reload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines = self
+            .updated
+            .iter()
+            .map(|path| format!("reloaded {}", path.display()))
+            .chain(
+                self.removed
+                    .iter()
+                    .map(|path| format!("removed {}", path.display())),
+            )
+            .chain(self.errors.iter().cloned());
+        write!(f, "{}", lines.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+impl WatchedRepository {
+    /// Fails like `Repository::load` when a file of `dir` does not parse
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let watched = WatchedRepository {
+            dir: dir.to_path_buf(),
+            current: RwLock::new(Arc::new(Repository::from_sources(&[])?)),
+            files: Mutex::new(Files::default()),
+        };
+        let reload = watched.reload();
+        if !reload.errors.is_empty() {
+            return Err(reload.errors.join("\n"));
+        }
+        Ok(watched)
+    }
+
+    /// The repository as of the last successful reload
+    pub fn current(&self) -> Arc<Repository> {
+        match self.current.read() {
+            Ok(current) => Arc::clone(&current),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    /// Reads the files that changed since the last reload
+    pub fn reload(&self) -> Reload {
+        let mut reload = Reload::default();
+        let paths = match source_files(&self.dir) {
+            Ok(paths) => paths,
+            Err(e) => {
+                reload.errors.push(e);
+                return reload;
+            }
+        };
+
+        let mut files = match self.files.lock() {
+            Ok(files) => files,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut sources = files.sources.clone();
+        for path in files.stamps.keys() {
+            if !paths.contains(path) && sources.remove(path).is_some() {
+                reload.removed.push(path.clone());
+            }
+        }
+
+        let mut stamps: BTreeMap<PathBuf, Stamp> = BTreeMap::new();
+        for path in paths {
+            let Ok(metadata) = fs::metadata(&path) else {
+                // gone since the directory was read
+                continue;
+            };
+            let stamp = (metadata.len(), metadata.modified().ok());
+            stamps.insert(path.clone(), stamp);
+            if files.stamps.get(&path) == Some(&stamp) {
+                continue;
+            }
+
+            let read = fs::read_to_string(&path).map_err(|e| e.to_string());
+            match read.and_then(|contents| parse_source(&path, &contents).map(|_| contents)) {
+                Ok(contents) => {
+                    sources.insert(path.clone(), contents);
+                    reload.updated.push(path);
+                }
+                Err(e) => reload.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if reload.updated.is_empty() && reload.removed.is_empty() {
+            files.stamps = stamps;
+            return reload;
+        }
+        let listed: Vec<(PathBuf, String)> = sources
+            .iter()
+            .map(|(path, contents)| (path.clone(), contents.clone()))
+            .collect();
+        match Repository::from_sources(&listed) {
+            Ok(repository) => {
+                files.sources = sources;
+                files.stamps = stamps;
+                match self.current.write() {
+                    Ok(mut current) => *current = Arc::new(repository),
+                    Err(poisoned) => *poisoned.into_inner() = Arc::new(repository),
+                }
+            }
+            Err(e) => {
+                // forget the changes, another file may change so that they fit
+                for path in reload.updated.iter().chain(reload.removed.iter()) {
+                    match files.stamps.get(path) {
+                        Some(stamp) => stamps.insert(path.clone(), *stamp),
+                        None => stamps.remove(path),
+                    };
+                }
+                files.stamps = stamps;
+                reload.errors.push(format!("{}: {}", self.dir.display(), e));
+                reload.updated.clear();
+                reload.removed.clear();
+            }
+        }
+        reload
+    }
+
+    /// Reloads every `interval` until the repository is dropped, `on_reload` hears about every reload that was not empty
+    pub fn watch(
+        self: &Arc<Self>,
+        interval: Duration,
+        on_reload: impl Fn(&Reload) + Send + 'static,
+    ) -> JoinHandle<()> {
+        let watched = Arc::downgrade(self);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            let Some(watched) = watched.upgrade() else {
+                return;
+            };
+            let reload = watched.reload();
+            if !reload.is_empty() {
+                on_reload(&reload);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::tests::repository_dir;
+
+    fn decision_names(watched: &WatchedRepository) -> Vec<String> {
+        watched
+            .current()
+            .graph
+            .decisions()
+            .iter()
+            .map(|d| d.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn swaps_in_changes_and_keeps_last_good_version() -> Result<(), String> {
+        let dir = repository_dir("watch");
+        let watched = WatchedRepository::load(&dir)?;
+        assert_eq!(decision_names(&watched), vec!["Dish", "Beverage"]);
+        assert!(watched.reload().is_empty());
+
+        let before = watched.current();
+        fs::write(dir.join("adult.json"), r#"{ ">": [{ "var": "age" }, 20] }"#)
+            .map_err(|e| e.to_string())?;
+        let lunch = "## Lunch\n\n| season | lunch |\n|---|---:|\n| string | string |\n| ## | ## |\n| - | \"Soup\" |\n";
+        fs::write(dir.join("lunch.md"), lunch).map_err(|e| e.to_string())?;
+        let reload = watched.reload();
+        assert_eq!(
+            reload.updated,
+            vec![dir.join("adult.json"), dir.join("lunch.md")]
+        );
+        assert_eq!(decision_names(&watched), vec!["Dish", "Beverage", "Lunch"]);
+        // the old version stays with whoever holds it
+        assert_eq!(before.graph.decisions().len(), 2);
+
+        let adult = watched.current().handle(
+            "POST",
+            "/jsonlogic/evaluate",
+            r#"{ "rule": "adult", "data": { "age": 20 } }"#,
+        );
+        assert_eq!(adult.body, serde_json::json!({ "result": false }));
+
+        let broken = "| season | lunch |\n|---|---:|\n| string | string |\n| ## | ## |\n| - |\n";
+        fs::write(dir.join("lunch.md"), broken).map_err(|e| e.to_string())?;
+        let reload = watched.reload();
+        assert!(reload.updated.is_empty());
+        assert_eq!(reload.errors.len(), 1);
+        assert!(reload.to_string().contains("lunch.md"), "{}", reload);
+        assert_eq!(decision_names(&watched), vec!["Dish", "Beverage", "Lunch"]);
+        // not reported again until it changes
+        assert!(watched.reload().is_empty());
+
+        // a second Lunch parses on its own but clashes with the first
+        fs::write(dir.join("more.md"), lunch).map_err(|e| e.to_string())?;
+        let reload = watched.reload();
+        assert!(reload.updated.is_empty());
+        assert!(reload.errors[0].contains("duplicate decision name"));
+        fs::remove_file(dir.join("more.md")).map_err(|e| e.to_string())?;
+        assert!(watched.reload().is_empty());
+
+        // once the first Lunch is renamed the second one fits
+        fs::write(dir.join("more.md"), lunch).map_err(|e| e.to_string())?;
+        assert!(watched.reload().errors[0].contains("duplicate decision name"));
+        let brunch = lunch.replace("Lunch", "Brunch").replace("lunch", "brunch");
+        fs::write(dir.join("lunch.md"), brunch).map_err(|e| e.to_string())?;
+        let reload = watched.reload();
+        assert_eq!(
+            reload.updated,
+            vec![dir.join("lunch.md"), dir.join("more.md")]
+        );
+        assert_eq!(
+            decision_names(&watched),
+            vec!["Dish", "Beverage", "Brunch", "Lunch"]
+        );
+        fs::remove_file(dir.join("more.md")).map_err(|e| e.to_string())?;
+        assert_eq!(watched.reload().removed, vec![dir.join("more.md")]);
+
+        fs::remove_file(dir.join("lunch.md")).map_err(|e| e.to_string())?;
+        assert_eq!(watched.reload().removed, vec![dir.join("lunch.md")]);
+        assert_eq!(decision_names(&watched), vec!["Dish", "Beverage"]);
+
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn watches_in_the_background() -> Result<(), String> {
+        let dir = repository_dir("watch_thread");
+        let watched = Arc::new(WatchedRepository::load(&dir)?);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = watched.watch(Duration::from_millis(10), move |reload| {
+            let _ = sender.send(reload.to_string());
+        });
+
+        fs::write(dir.join("minor.json"), r#"{ "<": [{ "var": "age" }, 18] }"#)
+            .map_err(|e| e.to_string())?;
+        let message = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .map_err(|e| e.to_string())?;
+        assert_eq!(
+            message,
+            format!("reloaded {}", dir.join("minor.json").display())
+        );
+        assert!(watched.current().rules.contains_key("minor"));
+
+        drop(watched);
+        handle
+            .join()
+            .map_err(|_| "watch thread panicked".to_string())?;
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}